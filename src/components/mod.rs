@@ -1,60 +1,20 @@
-use crate::resources::MazeBuilderType;
 use bevy::prelude::*;
 
-#[derive(Component)]
-pub enum HudAction {
-    ToggleOverlay,
-    RandomiseSeed,
-    ChangeMazeType(MazeBuilderType),
-    None,
-}
-
-#[derive(Component, Eq, PartialEq, Copy, Clone)]
-pub enum ButtonVariant {
-    Normal,
-    // bool = is_toggle_active
-    Toggle(bool),
-    // bool = is_dropdown_open
-    DropdownOpener(bool),
-    // bool = is_option_active
-    Radio(bool),
-}
-
-#[derive(Component, Eq, PartialEq, Copy, Clone)]
-pub struct Dropdown;
-
-#[derive(Component, Eq, PartialEq, Copy, Clone)]
-pub struct DropdownMenu;
-
-#[derive(Component, Eq, PartialEq, Copy, Clone)]
-pub struct RadioGroup;
-
 #[derive(Component, Eq, PartialEq, Copy, Clone)]
 pub enum WallOrientation {
     Horizontal,
     Vertical,
 }
 
-#[derive(Component, Hash, Eq, PartialEq, Copy, Clone)]
+// What `from` and `to` mean depends on the grid the wall belongs to. On a
+// `GridMap` they're the corners at either end of the wall, so a horizontal wall
+// goes from a corner to the one to its right, and a vertical wall from a corner
+// to the one above it. The polar, hex, triangle and upsilon grids don't have
+// corners on whole numbers, so they use the two cells either side of the wall
+// instead, with the cell the grid numbers first as `from`. Either way, each wall
+// has exactly one `Wall`, so they can be compared and kept in sets.
+#[derive(Component, Hash, Eq, PartialEq, Copy, Clone, Debug)]
 pub struct Wall {
     pub from: IVec2,
     pub to: IVec2,
 }
-
-#[derive(Component, Hash, Eq, PartialEq, Copy, Clone)]
-pub struct Cell {
-    pub position: IVec2,
-}
-
-#[derive(Component)]
-pub struct CellContentText;
-#[derive(Component)]
-pub struct CellContentBackground;
-
-#[derive(Component)]
-pub struct CellOverlayText;
-#[derive(Component)]
-pub struct CellOverlayBackground;
-
-#[derive(Component)]
-pub struct OverlayVisibility(pub bool);
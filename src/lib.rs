@@ -0,0 +1,10 @@
+// The maze generation and solving code, usable without a window. The Bevy app in
+// `main.rs` is a consumer of this library.
+pub mod components;
+pub mod maze_builders;
+pub mod maze_post_processors;
+pub mod resources;
+pub mod utils;
+
+pub use crate::components::*;
+pub use crate::resources::*;
@@ -1,13 +1,17 @@
-use bevy::{prelude::*, utils::HashSet};
+use bevy::prelude::*;
+use mazes::maze_builders::{
+    AldousBroder, BinaryTreeBias, GrowingTreeStrategy, MazeBuilder, SidewinderBias,
+};
+use mazes::maze_post_processors::{Braid, Sparseness};
+use mazes::resources::*;
+use mazes::utils::load_grid_map_from_mask_file;
+use std::path::Path;
 
-mod components;
-mod maze_builders;
-mod resources;
 mod systems;
-mod utils;
+mod ui;
 
-pub use crate::resources::*;
 pub use crate::systems::*;
+pub use crate::ui::*;
 
 pub fn resource_updated<T>(res: Option<Res<T>>) -> bool
 where
@@ -19,15 +23,64 @@ where
     }
 }
 
+// The app can start from a mask file to carve a maze in that shape, for example
+// `cargo run -- --mask masks/circle.txt`.
+fn get_initial_grid_map() -> GridMap {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(flag_index) = args.iter().position(|arg| arg == "--mask") else {
+        return GridMap::new(15, 15);
+    };
+    let Some(mask_path) = args.get(flag_index + 1) else {
+        eprintln!("--mask needs a path to a .txt, .png, .pgm, .ppm or .pbm file");
+        std::process::exit(1);
+    };
+
+    match load_grid_map_from_mask_file(Path::new(mask_path)) {
+        Ok(grid_map) => grid_map,
+        Err(error) => {
+            eprintln!("Couldn't load the mask '{mask_path}': {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn get_triangle_grid(columns: i32, rows: i32) -> TriangleGrid {
+    match TriangleGrid::new(columns, rows) {
+        Ok(triangle_grid) => triangle_grid,
+        Err(error) => {
+            eprintln!("Couldn't make the triangle grid: {error}");
+            std::process::exit(1);
+        }
+    }
+}
+
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins)
-        .insert_resource(GridMap::new(15, 15))
+        .insert_resource(GridType::default())
+        .insert_resource(get_initial_grid_map())
+        .insert_resource(PolarGrid::new(8))
+        .insert_resource(HexGrid::new(15, 15))
+        .insert_resource(get_triangle_grid(29, 15))
+        .insert_resource(UpsilonGrid::new(15, 15))
+        .insert_resource(LayeredGrid::new(10, 10, 3))
+        .insert_resource(LayeredMaze::default())
+        .insert_resource(VisibleLevel::default())
+        .insert_resource(WeaveGrid::new(15, 15))
+        .insert_resource(WeaveMaze::default())
         .insert_resource(RngSeed(0))
-        .insert_resource(MazeBuilderType::AldousBroder)
+        .insert_resource(MazeBuilderRegistry::default())
+        .insert_resource(SelectedMazeBuilder(String::from(AldousBroder.name())))
+        .insert_resource(BinaryTreeBias::default())
+        .insert_resource(SidewinderBias::default())
+        .insert_resource(GrowingTreeStrategy::default())
+        .insert_resource(Braid::default())
+        .insert_resource(Sparseness::default())
         .insert_resource(OverlayState(false))
-        .insert_resource(RemovedWalls(HashSet::new()))
-        .insert_resource(Solution { ..default() })
+        .insert_resource(LivingMaze::default())
+        .insert_resource(RemovedWalls(WallSet::default()))
+        .insert_resource(InaccessibleCells::default())
+        .insert_resource(Solution::<IVec2>::default())
         .add_systems(
             Startup,
             (
@@ -48,11 +101,53 @@ fn main() {
                     update_radio_state,
                 ),
                 handle_hud_action,
+                update_binary_tree_builder
+                    .run_if(resource_updated::<BinaryTreeBias>)
+                    .before(update_maze_resources),
+                update_sidewinder_builder
+                    .run_if(resource_updated::<SidewinderBias>)
+                    .before(update_maze_resources),
+                update_growing_tree_builder
+                    .run_if(resource_updated::<GrowingTreeStrategy>)
+                    .before(update_maze_resources),
+                update_maze_builder_settings_visibility
+                    .run_if(resource_updated::<SelectedMazeBuilder>),
+                update_grid_type_settings_visibility.run_if(resource_updated::<GridType>),
+                update_maze_builder_dropdown.run_if(resource_updated::<MazeBuilderRegistry>),
+                update_maze_builder_description.run_if(
+                    resource_updated::<SelectedMazeBuilder>
+                        .or(resource_updated::<MazeBuilderRegistry>),
+                ),
                 update_maze_resources.run_if(
                     resource_updated::<RngSeed>
-                        .or(resource_updated::<MazeBuilderType>)
-                        .or(resource_updated::<GridMap>),
+                        .or(resource_updated::<SelectedMazeBuilder>)
+                        .or(resource_updated::<MazeBuilderRegistry>)
+                        .or(resource_updated::<Braid>)
+                        .or(resource_updated::<Sparseness>)
+                        .or(resource_updated::<GridMap>)
+                        .or(resource_updated::<GridType>),
                 ),
+                update_visible_level
+                    .run_if(
+                        resource_updated::<VisibleLevel>
+                            .and(|grid_type: Res<GridType>| *grid_type == GridType::Layered),
+                    )
+                    .after(update_maze_resources),
+                (despawn_grid_map, setup_grid_map)
+                    .chain()
+                    .run_if(
+                        resource_updated::<GridType>
+                            .or(resource_updated::<GridMap>)
+                            .or(resource_updated::<VisibleLevel>)
+                            .or(resource_updated::<WeaveMaze>),
+                    )
+                    .after(update_visible_level),
+                // Origin Shift only knows how to shift a square grid map.
+                update_living_maze
+                    .run_if(|living_maze: Res<LivingMaze>, grid_type: Res<GridType>| {
+                        living_maze.is_enabled && *grid_type == GridType::Square
+                    })
+                    .after(update_maze_resources),
                 (update_cell_content, update_wall_visibility)
                     .run_if(resource_updated::<RemovedWalls>),
                 update_overlay_visibility.run_if(resource_updated::<OverlayState>),
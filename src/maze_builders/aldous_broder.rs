@@ -1,58 +1,92 @@
+use super::{CellSet, MazeBuilder};
 use crate::components::Wall;
-use crate::resources::GridMap;
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet};
 use bevy::{prelude::*, utils::HashSet};
 use fastrand::Rng;
 
-pub fn carve_aldous_broder_into_grid_map(grid_map: &GridMap, rng_seed: u64) -> HashSet<Wall> {
-    let mut rng = Rng::with_seed(rng_seed);
-    let mut removed_walls = HashSet::new();
-    let mut visited = HashSet::new();
-
-    let cell_count = grid_map.get_cell_count() as usize;
-    let random_start = rng.usize(0..cell_count);
-    let mut current_pos = grid_map.index_to_cell_pos(random_start as i32).unwrap();
-    visited.insert(current_pos);
-
-    while visited.len() < cell_count {
-        let neighbours = vec![
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::NORTH),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::NORTH),
-            ),
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::EAST),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::EAST),
-            ),
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::SOUTH),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::SOUTH),
-            ),
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::WEST),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::WEST),
-            ),
-        ];
-
-        let possible_neighbours: Vec<(Option<IVec2>, Option<Wall>)> = neighbours
-            .into_iter()
-            .filter(|(neighbour, wall)| neighbour.is_some() && wall.is_some())
-            .collect();
-
-        let num_possible_neighbours = possible_neighbours.len();
-        let random_neighbour_index = rng.usize(0..(num_possible_neighbours));
-        let neighbour = possible_neighbours.iter().nth(random_neighbour_index);
-
-        if let Some((Some(neighbour_cell), Some(neighbour_wall))) = neighbour {
-            if !visited.contains(neighbour_cell) {
-                removed_walls.insert(*neighbour_wall);
-            }
-            current_pos = *neighbour_cell;
-            visited.insert(current_pos);
-        } else {
+#[deprecated(note = "use `carve_aldous_broder_into_grid`, which carves any `Grid`")]
+pub fn carve_aldous_broder_into_grid_map(grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+    carve_aldous_broder_into_grid(grid_map, rng)
+}
+
+pub fn carve_aldous_broder_into_grid<G: Grid + ?Sized>(grid: &G, rng: &mut Rng) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    let Some(start_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visited.insert(grid, start_pos);
+    let cell_count = grid.cell_count();
+
+    carve_random_walk(
+        grid,
+        rng,
+        start_pos,
+        cell_count,
+        &mut visited,
+        &mut removed_walls,
+    );
+
+    removed_walls
+}
+
+// Randomly walks from `start_pos`, carving into each unvisited cell it steps into,
+// until `target_visited_count` cells have been visited.
+fn carve_random_walk<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    start_pos: G::Cell,
+    target_visited_count: usize,
+    visited: &mut CellSet,
+    removed_walls: &mut G::LinkSet,
+) {
+    let mut current_pos = start_pos;
+
+    while visited.len() < target_visited_count {
+        let neighbours = grid.neighbour_walls(current_pos);
+        if neighbours.is_empty() {
             // Something went wrong, a neighbour couldn't be found.
             break;
         }
+
+        let random_neighbour_index = rng.usize(0..neighbours.len());
+        let (neighbour_cell, neighbour_wall) = neighbours[random_neighbour_index];
+        if visited.insert(grid, neighbour_cell) {
+            removed_walls.insert(neighbour_wall);
+        }
+        current_pos = neighbour_cell;
     }
+}
 
-    removed_walls
+pub struct AldousBroder;
+
+impl MazeBuilder for AldousBroder {
+    fn name(&self) -> &str {
+        "Aldous Broder"
+    }
+
+    fn description(&self) -> &str {
+        "A random walk that carves into every unvisited cell it steps into. Produces an unbiased maze, but slows down as fewer unvisited cells remain."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_aldous_broder_into_grid(grid_map, rng)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_aldous_broder_into_grid(grid, rng))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_aldous_broder_into_grid(grid, rng))
+    }
 }
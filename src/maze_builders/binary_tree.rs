@@ -1,23 +1,68 @@
-use super::get_direction_from_coinflip;
-use crate::components::Wall;
-use crate::resources::GridMap;
-use bevy::utils::HashSet;
+use super::{get_direction_from_coinflip, join_masked_regions, MazeBuilder};
+use crate::resources::{Grid, GridMap, WallSet};
+use bevy::prelude::*;
 use fastrand::Rng;
 
-pub fn carve_binary_tree_into_grid_map(grid_map: &GridMap, rng_seed: u64) -> HashSet<Wall> {
-    let mut rng = Rng::with_seed(rng_seed);
-    let mut removed_walls = HashSet::new();
+// The pair of directions the binary tree carves in, which decides the two
+// edges of the grid map that end up as open corridors.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Diagonal {
+    NorthEast,
+    NorthWest,
+    SouthEast,
+    SouthWest,
+}
+
+impl Diagonal {
+    // Returns the (vertical, horizontal) directions.
+    pub fn get_directions(&self) -> (Dir2, Dir2) {
+        match self {
+            Diagonal::NorthEast => (Dir2::NORTH, Dir2::EAST),
+            Diagonal::NorthWest => (Dir2::NORTH, Dir2::WEST),
+            Diagonal::SouthEast => (Dir2::SOUTH, Dir2::EAST),
+            Diagonal::SouthWest => (Dir2::SOUTH, Dir2::WEST),
+        }
+    }
+}
+
+#[derive(Resource, Copy, Clone, PartialEq)]
+pub struct BinaryTreeBias {
+    pub diagonal: Diagonal,
+    // The chance of carving in the diagonal's vertical direction rather than its
+    // horizontal direction.
+    pub vertical_probability: f32,
+}
+
+impl Default for BinaryTreeBias {
+    fn default() -> Self {
+        BinaryTreeBias {
+            diagonal: Diagonal::NorthEast,
+            vertical_probability: 0.5,
+        }
+    }
+}
+
+pub fn carve_binary_tree_into_grid_map(
+    grid_map: &GridMap,
+    rng: &mut Rng,
+    bias: BinaryTreeBias,
+) -> WallSet {
+    let mut removed_walls = grid_map.new_link_set();
+    let directions = bias.diagonal.get_directions();
+    // The edges of the grid map are still edges when it wraps around, otherwise
+    // the cells along them would carve all the way around in a loop.
+    let unwrapped_grid_map = grid_map.without_wrapping();
 
     grid_map.iter_cells().for_each(|cell_pos| {
-        let coinflip = rng.bool();
-        let mut direction = get_direction_from_coinflip(coinflip);
-        let mut wall = grid_map.inner_wall_from_cell_pos(cell_pos, direction);
+        let coinflip = rng.f32() < bias.vertical_probability;
+        let mut direction = get_direction_from_coinflip(coinflip, directions);
+        let mut wall = unwrapped_grid_map.inner_wall_from_cell_pos(cell_pos, direction);
 
         // Switch direction if there's no neighbour in that direction.
         // This avoids carving through the outer walls.
         if wall.is_none() {
-            direction = get_direction_from_coinflip(!coinflip);
-            wall = grid_map.inner_wall_from_cell_pos(cell_pos, direction);
+            direction = get_direction_from_coinflip(!coinflip, directions);
+            wall = unwrapped_grid_map.inner_wall_from_cell_pos(cell_pos, direction);
         }
 
         // Check the neighbor again, we might be in the far corner, and so should
@@ -27,5 +72,137 @@ pub fn carve_binary_tree_into_grid_map(grid_map: &GridMap, rng_seed: u64) -> Has
         }
     });
 
-    removed_walls
+    join_masked_regions(grid_map, rng, removed_walls)
+}
+
+#[derive(Default)]
+pub struct BinaryTree {
+    pub bias: BinaryTreeBias,
+}
+
+impl MazeBuilder for BinaryTree {
+    fn name(&self) -> &str {
+        "Binary Tree"
+    }
+
+    fn description(&self) -> &str {
+        "Carves in one of two directions from every cell. Very fast, but leaves long corridors along two edges of the maze."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_binary_tree_into_grid_map(grid_map, rng, self.bias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    fn is_wall_removed(
+        grid_map: &GridMap,
+        removed_walls: &WallSet,
+        cell_pos: IVec2,
+        direction: Dir2,
+    ) -> bool {
+        grid_map
+            .inner_wall_from_cell_pos(cell_pos, direction)
+            .is_some_and(|wall| removed_walls.contains(&wall))
+    }
+
+    #[test]
+    fn each_diagonal_leaves_corridors_along_its_two_edges() {
+        let grid_map = GridMap::new(10, 8);
+        let diagonals = [
+            Diagonal::NorthEast,
+            Diagonal::NorthWest,
+            Diagonal::SouthEast,
+            Diagonal::SouthWest,
+        ];
+
+        for diagonal in diagonals {
+            let bias = BinaryTreeBias {
+                diagonal,
+                vertical_probability: 0.5,
+            };
+            let removed_walls =
+                carve_binary_tree_into_grid_map(&grid_map, &mut Rng::with_seed(0), bias);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+
+            // The row at the vertical edge runs all the way along the horizontal
+            // direction, and the column at the horizontal edge all the way along
+            // the vertical direction.
+            let (vertical, horizontal) = diagonal.get_directions();
+            let edge_row = match vertical {
+                Dir2::NORTH => grid_map.get_rows() - 1,
+                _ => 0,
+            };
+            let edge_column = match horizontal {
+                Dir2::EAST => grid_map.get_columns() - 1,
+                _ => 0,
+            };
+            let row_cells = (0..grid_map.get_columns())
+                .map(|x| IVec2::new(x, edge_row))
+                .filter(|cell_pos| cell_pos.x != edge_column);
+            let column_cells = (0..grid_map.get_rows())
+                .map(|y| IVec2::new(edge_column, y))
+                .filter(|cell_pos| cell_pos.y != edge_row);
+            assert!(row_cells.into_iter().all(|cell_pos| is_wall_removed(
+                &grid_map,
+                &removed_walls,
+                cell_pos,
+                horizontal
+            )));
+            assert!(column_cells.into_iter().all(|cell_pos| is_wall_removed(
+                &grid_map,
+                &removed_walls,
+                cell_pos,
+                vertical
+            )));
+
+            // Every cell carves one of its two walls towards the diagonal, apart
+            // from the corner at the end of both corridors.
+            for cell_pos in grid_map.iter_cells() {
+                let carved_count = [vertical, horizontal]
+                    .into_iter()
+                    .filter(|direction| {
+                        is_wall_removed(&grid_map, &removed_walls, cell_pos, *direction)
+                    })
+                    .count();
+                let is_corner = cell_pos == IVec2::new(edge_column, edge_row);
+                assert_eq!(carved_count, if is_corner { 0 } else { 1 });
+            }
+        }
+    }
+
+    #[test]
+    fn vertical_probability_decides_which_way_cells_carve() {
+        let grid_map = GridMap::new(10, 8);
+        for (vertical_probability, is_vertical) in [(1.0, true), (0.0, false)] {
+            let bias = BinaryTreeBias {
+                diagonal: Diagonal::NorthEast,
+                vertical_probability,
+            };
+            let removed_walls =
+                carve_binary_tree_into_grid_map(&grid_map, &mut Rng::with_seed(0), bias);
+
+            // Apart from along the edges, where there's only one way to go.
+            for cell_pos in grid_map.iter_cells() {
+                let is_top = cell_pos.y == grid_map.get_rows() - 1;
+                let is_right = cell_pos.x == grid_map.get_columns() - 1;
+                if is_top || is_right {
+                    continue;
+                }
+                let direction = if is_vertical { Dir2::NORTH } else { Dir2::EAST };
+                assert!(is_wall_removed(
+                    &grid_map,
+                    &removed_walls,
+                    cell_pos,
+                    direction
+                ));
+            }
+        }
+    }
 }
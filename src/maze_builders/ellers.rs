@@ -0,0 +1,205 @@
+use super::{join_masked_regions, MazeBuilder};
+use crate::components::Wall;
+use crate::resources::{Grid, GridMap, WallSet};
+use crate::utils::DisjointSet;
+use bevy::prelude::*;
+use fastrand::Rng;
+
+// The walls removed while carving a single row, which are the passages east
+// between cells in the row and the passages north into the next row.
+pub struct EllersRow {
+    pub y: i32,
+    pub removed_walls: Vec<Wall>,
+}
+
+// Generates an Eller's maze one row at a time, from south to north. Only the
+// current row's sets are kept, so tall or endless mazes use constant memory.
+pub struct EllersRowIterator<'a> {
+    rng: &'a mut Rng,
+    columns: i32,
+    // `None` produces rows endlessly.
+    rows: Option<i32>,
+    y: i32,
+    // The set each cell in the current row belongs to, labelled by the column of
+    // a cell in the row below that was in the same set. Cells that weren't carved
+    // into from the row below don't belong to a set yet.
+    sets: Vec<Option<usize>>,
+}
+
+impl<'a> EllersRowIterator<'a> {
+    pub fn new(columns: i32, rows: Option<i32>, rng: &'a mut Rng) -> Self {
+        EllersRowIterator {
+            rng,
+            columns,
+            rows,
+            y: 0,
+            sets: vec![None; columns.max(0) as usize],
+        }
+    }
+}
+
+fn get_east_wall(cell_pos: IVec2) -> Wall {
+    Wall {
+        from: IVec2 {
+            x: cell_pos.x + 1,
+            y: cell_pos.y,
+        },
+        to: cell_pos + 1,
+    }
+}
+
+fn get_north_wall(cell_pos: IVec2) -> Wall {
+    Wall {
+        from: IVec2 {
+            x: cell_pos.x,
+            y: cell_pos.y + 1,
+        },
+        to: cell_pos + 1,
+    }
+}
+
+impl Iterator for EllersRowIterator<'_> {
+    type Item = EllersRow;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.columns <= 0 || self.rows.is_some_and(|rows| self.y >= rows) {
+            return None;
+        }
+
+        let y = self.y;
+        let is_last_row = self.rows.is_some_and(|rows| y == rows - 1);
+        let mut removed_walls = vec![];
+
+        // Cells that were carved into from the same set below are already joined,
+        // the rest start in a set of their own.
+        let column_count = self.sets.len();
+        let mut row_sets = DisjointSet::new(column_count);
+        let mut first_cells = vec![None; column_count];
+        self.sets.iter().enumerate().for_each(|(x, set)| {
+            if let Some(set) = *set {
+                let first_cell = *first_cells[set].get_or_insert(x);
+                row_sets.union(first_cell, x);
+            }
+        });
+
+        // Randomly join neighbouring cells that are in different sets. The last row
+        // joins all of them, so that the maze is fully connected.
+        for x in 0..column_count.saturating_sub(1) {
+            if row_sets.is_connected(x, x + 1) || !(is_last_row || self.rng.bool()) {
+                continue;
+            }
+
+            removed_walls.push(get_east_wall(IVec2 { x: x as i32, y }));
+            row_sets.union(x, x + 1);
+        }
+
+        if !is_last_row {
+            // Group the cells by set, in the order the sets first appear in the row
+            // so that the result is reproducible.
+            let mut set_order = vec![];
+            let mut set_cells = vec![vec![]; column_count];
+            (0..column_count).for_each(|x| {
+                let set = row_sets.find(x);
+                if set_cells[set].is_empty() {
+                    set_order.push(set);
+                }
+                set_cells[set].push(x);
+            });
+
+            // Every set has to carve north at least once, otherwise it would be cut
+            // off from the rest of the maze.
+            let mut next_sets = vec![None; column_count];
+            set_order.into_iter().for_each(|set| {
+                let cells = &mut set_cells[set];
+                self.rng.shuffle(cells);
+                let carve_count = self.rng.usize(1..=cells.len());
+                cells.iter().take(carve_count).for_each(|x| {
+                    removed_walls.push(get_north_wall(IVec2 { x: *x as i32, y }));
+                    next_sets[*x] = Some(set);
+                });
+            });
+            self.sets = next_sets;
+        }
+
+        self.y += 1;
+        Some(EllersRow { y, removed_walls })
+    }
+}
+
+// The rows don't know about cells that are turned off, so walls next to them are
+// dropped afterwards.
+pub fn carve_ellers_into_grid_map(grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+    let mut removed_walls = grid_map.new_link_set();
+    removed_walls.extend(
+        EllersRowIterator::new(grid_map.get_columns(), Some(grid_map.get_rows()), rng)
+            .flat_map(|row| row.removed_walls)
+            .filter(|wall| grid_map.cells_from_inner_wall(*wall).is_some()),
+    );
+
+    join_masked_regions(grid_map, rng, removed_walls)
+}
+
+pub struct Ellers;
+
+impl MazeBuilder for Ellers {
+    fn name(&self) -> &str {
+        "Eller's"
+    }
+
+    fn description(&self) -> &str {
+        "Carves one row at a time, tracking which cells in the row are connected. Only needs one row of state, so can generate endless mazes."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_ellers_into_grid_map(grid_map, rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    #[test]
+    fn rows_only_carve_within_and_north_of_themselves() {
+        let mut rng = Rng::with_seed(0);
+        let rows: Vec<EllersRow> = EllersRowIterator::new(10, Some(6), &mut rng).collect();
+        assert_eq!(
+            rows.iter().map(|row| row.y).collect::<Vec<_>>(),
+            (0..6).collect::<Vec<_>>()
+        );
+
+        for row in &rows {
+            row.removed_walls.iter().for_each(|wall| {
+                let is_east = wall.to - wall.from == IVec2::Y && wall.from.y == row.y;
+                let is_north = wall.to - wall.from == IVec2::X && wall.from.y == row.y + 1;
+                assert!(is_east || is_north);
+            });
+        }
+        // The last row only joins its cells, there's nothing north of it.
+        let last_row = rows.last().unwrap();
+        assert!(last_row.removed_walls.iter().all(|wall| wall.from.y == 5));
+    }
+
+    #[test]
+    fn endless_rows_keep_one_row_of_state() {
+        let mut rng = Rng::with_seed(0);
+        let mut rows = EllersRowIterator::new(25, None, &mut rng);
+        for _ in 0..1000 {
+            assert!(rows.next().is_some());
+            assert_eq!(rows.sets.len(), 25);
+            assert!(rows.sets.iter().flatten().all(|set| *set < 25));
+        }
+    }
+
+    #[test]
+    fn carves_a_spanning_tree() {
+        let grid_map = GridMap::new(30, 20);
+        for seed in 0..10 {
+            let removed_walls = carve_ellers_into_grid_map(&grid_map, &mut Rng::with_seed(seed));
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+}
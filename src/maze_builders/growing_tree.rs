@@ -0,0 +1,215 @@
+use super::{CellSet, MazeBuilder};
+use crate::components::Wall;
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+use std::collections::VecDeque;
+
+// How the growing tree picks the next active cell to carve from.
+#[derive(Resource, Copy, Clone, PartialEq)]
+pub enum GrowingTreeStrategy {
+    // Behaves like the recursive backtracker.
+    Newest,
+    // Behaves like simplified Prim's.
+    Random,
+    Oldest,
+    // Picks the newest cell with a probability of `newest_ratio`, otherwise a random cell.
+    Mixed { newest_ratio: f32 },
+}
+
+impl Default for GrowingTreeStrategy {
+    fn default() -> Self {
+        GrowingTreeStrategy::Mixed { newest_ratio: 0.75 }
+    }
+}
+
+// The active cells in the order they were added, so that both the newest and
+// oldest cells can be cheaply taken from either end. Removing a cell from the
+// middle would shift every cell after it, so it leaves a gap instead, and the
+// gaps are cleared out once they're half of the list.
+struct ActiveCells<C> {
+    cells: VecDeque<Option<C>>,
+    len: usize,
+}
+
+impl<C: Copy> ActiveCells<C> {
+    fn new(cell: C) -> Self {
+        ActiveCells {
+            cells: VecDeque::from([Some(cell)]),
+            len: 1,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn push(&mut self, cell: C) {
+        self.cells.push_back(Some(cell));
+        self.len += 1;
+    }
+
+    // The ends are never gaps, so the newest and oldest cells are at either end.
+    fn pick(&self, strategy: GrowingTreeStrategy, rng: &mut Rng) -> (usize, C) {
+        let index = match strategy {
+            GrowingTreeStrategy::Newest => self.cells.len() - 1,
+            GrowingTreeStrategy::Random => self.get_random_index(rng),
+            GrowingTreeStrategy::Oldest => 0,
+            GrowingTreeStrategy::Mixed { newest_ratio } => {
+                if rng.f32() < newest_ratio {
+                    self.cells.len() - 1
+                } else {
+                    self.get_random_index(rng)
+                }
+            }
+        };
+        (index, self.cells[index].unwrap())
+    }
+
+    // At most half of the list is gaps, so this doesn't take many tries.
+    fn get_random_index(&self, rng: &mut Rng) -> usize {
+        loop {
+            let index = rng.usize(0..self.cells.len());
+            if self.cells[index].is_some() {
+                return index;
+            }
+        }
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.cells[index] = None;
+        self.len -= 1;
+        while self.cells.back().is_some_and(Option::is_none) {
+            self.cells.pop_back();
+        }
+        while self.cells.front().is_some_and(Option::is_none) {
+            self.cells.pop_front();
+        }
+        if self.len * 2 < self.cells.len() {
+            self.cells.retain(Option::is_some);
+        }
+    }
+}
+
+pub fn carve_growing_tree_into_grid<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    strategy: GrowingTreeStrategy,
+) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    let Some(start_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visited.insert(grid, start_pos);
+
+    let mut active = ActiveCells::new(start_pos);
+
+    while !active.is_empty() {
+        let (active_index, active_cell) = active.pick(strategy, rng);
+
+        let unvisited_neighbours: Vec<(G::Cell, G::Link)> = grid
+            .neighbour_walls(active_cell)
+            .into_iter()
+            .filter(|(neighbour, _)| !visited.contains(grid, *neighbour))
+            .collect();
+
+        // Cells with no unvisited neighbours can't grow the maze any further.
+        if unvisited_neighbours.is_empty() {
+            active.remove(active_index);
+            continue;
+        }
+
+        let random_neighbour_index = rng.usize(0..unvisited_neighbours.len());
+        let (neighbour_cell, neighbour_wall) = unvisited_neighbours[random_neighbour_index];
+        removed_walls.insert(neighbour_wall);
+        visited.insert(grid, neighbour_cell);
+        active.push(neighbour_cell);
+    }
+
+    removed_walls
+}
+
+#[derive(Default)]
+pub struct GrowingTree {
+    pub strategy: GrowingTreeStrategy,
+}
+
+impl MazeBuilder for GrowingTree {
+    fn name(&self) -> &str {
+        "Growing Tree"
+    }
+
+    fn description(&self) -> &str {
+        "Grows the maze from a list of active cells, picking the next one to carve from using a configurable strategy. Can behave like the recursive backtracker, Prim's, or a mix of both."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_growing_tree_into_grid(grid_map, rng, self.strategy)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_growing_tree_into_grid(grid, rng, self.strategy))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_growing_tree_into_grid(grid, rng, self.strategy))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    #[test]
+    fn every_strategy_carves_a_spanning_tree() {
+        let grid_map = GridMap::new(20, 15);
+        let strategies = [
+            GrowingTreeStrategy::Newest,
+            GrowingTreeStrategy::Random,
+            GrowingTreeStrategy::Oldest,
+            GrowingTreeStrategy::Mixed { newest_ratio: 0.5 },
+        ];
+
+        for strategy in strategies {
+            let removed_walls =
+                carve_growing_tree_into_grid(&grid_map, &mut Rng::with_seed(0), strategy);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+
+    #[test]
+    fn newest_cell_is_still_picked_after_removing_from_the_middle() {
+        let strategies = [
+            GrowingTreeStrategy::Newest,
+            GrowingTreeStrategy::Mixed { newest_ratio: 1.0 },
+        ];
+
+        for strategy in strategies {
+            let mut active = ActiveCells::new(0);
+            (1..5).for_each(|cell| active.push(cell));
+            active.remove(1);
+            assert_eq!(active.pick(strategy, &mut Rng::with_seed(0)).1, 4);
+        }
+
+        // The oldest is still the first cell left once the cells before it are gone.
+        let mut active = ActiveCells::new(0);
+        (1..5).for_each(|cell| active.push(cell));
+        active.remove(1);
+        active.remove(0);
+        let rng = &mut Rng::with_seed(0);
+        assert_eq!(active.pick(GrowingTreeStrategy::Oldest, rng), (0, 2));
+    }
+}
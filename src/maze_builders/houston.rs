@@ -0,0 +1,292 @@
+use super::{wilson::carve_loop_erased_walks, CellSet, MazeBuilder};
+use crate::components::Wall;
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+
+// Aldous-Broder is quick to visit the first cells but slow to find the last
+// few, while Wilson's is the opposite. This runs Aldous-Broder until the
+// `switch_fraction` of the cells nearest to where it starts are visited, then
+// finishes with Wilson's.
+//
+// Only the passages joining those nearest cells back to the start are kept.
+// Keeping everything Aldous-Broder carved would bias the maze, as which cells
+// it's visited by then depends on the walk. The cells are picked before the walk
+// starts though, so the passages joining them are the ones a uniform maze would
+// have, and Wilson's finishes the maze uniformly around them. The walk still has
+// to find the last few of the nearest cells, so this takes fewer steps than
+// Aldous-Broder, but not fewer than Wilson's. A `switch_fraction` of 1.0 is plain
+// Aldous-Broder, and 0.0 is plain Wilson's.
+pub fn carve_houston_into_grid<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    switch_fraction: f32,
+) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    let Some(start_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visited.insert(grid, start_pos);
+    let cell_count = grid.cell_count();
+
+    let switch_count = (cell_count as f32 * switch_fraction.clamp(0.0, 1.0)).ceil() as usize;
+    let nearest_cells = get_nearest_cells(grid, start_pos, switch_count);
+    let entrances = get_random_walk_entrances(grid, rng, start_pos, &nearest_cells);
+
+    // Each nearest cell's path back to the start follows the way the walk first
+    // came into each cell, until it meets a path that's already been carved.
+    for cell_pos in nearest_cells {
+        let mut current_pos = cell_pos;
+        while !visited.contains(grid, current_pos) {
+            let Some((previous_pos, wall)) = grid
+                .cell_index(current_pos)
+                .and_then(|index| entrances[index])
+            else {
+                break;
+            };
+            visited.insert(grid, current_pos);
+            removed_walls.insert(wall);
+            current_pos = previous_pos;
+        }
+    }
+    carve_loop_erased_walks(grid, rng, &mut visited, &mut removed_walls);
+
+    removed_walls
+}
+
+// Returns up to `count` cells, nearest to `start_pos` first.
+fn get_nearest_cells<G: Grid + ?Sized>(grid: &G, start_pos: G::Cell, count: usize) -> Vec<G::Cell> {
+    let mut nearest_cells = vec![start_pos];
+    let mut seen = CellSet::new(grid);
+    seen.insert(grid, start_pos);
+    let mut next_index = 0;
+
+    while nearest_cells.len() < count && next_index < nearest_cells.len() {
+        let cell_pos = nearest_cells[next_index];
+        next_index += 1;
+        for neighbour in grid.neighbours(cell_pos) {
+            if nearest_cells.len() < count && seen.insert(grid, neighbour) {
+                nearest_cells.push(neighbour);
+            }
+        }
+    }
+
+    nearest_cells
+}
+
+// Randomly walks from `start_pos` until every one of `cells` has been visited,
+// and returns the cell the walk first came into each cell from, with the wall
+// between them, by cell index.
+fn get_random_walk_entrances<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    start_pos: G::Cell,
+    cells: &[G::Cell],
+) -> Vec<Option<(G::Cell, G::Link)>> {
+    let mut entrances = vec![None; grid.cell_index_count()];
+    let mut visited = CellSet::new(grid);
+    visited.insert(grid, start_pos);
+    let mut targets = CellSet::new(grid);
+    cells.iter().for_each(|cell_pos| {
+        targets.insert(grid, *cell_pos);
+    });
+    let mut unvisited_target_count = cells
+        .iter()
+        .filter(|cell_pos| **cell_pos != start_pos)
+        .count();
+    let mut current_pos = start_pos;
+
+    while unvisited_target_count > 0 {
+        let neighbours = grid.neighbour_walls(current_pos);
+        if neighbours.is_empty() {
+            // Something went wrong, a neighbour couldn't be found.
+            break;
+        }
+
+        let random_neighbour_index = rng.usize(0..neighbours.len());
+        let (neighbour_cell, neighbour_wall) = neighbours[random_neighbour_index];
+        if visited.insert(grid, neighbour_cell) {
+            if let Some(index) = grid.cell_index(neighbour_cell) {
+                entrances[index] = Some((current_pos, neighbour_wall));
+            }
+            if targets.contains(grid, neighbour_cell) {
+                unvisited_target_count -= 1;
+            }
+        }
+        current_pos = neighbour_cell;
+    }
+
+    entrances
+}
+
+pub struct Houston {
+    pub switch_fraction: f32,
+}
+
+impl Default for Houston {
+    fn default() -> Self {
+        Houston {
+            switch_fraction: 0.5,
+        }
+    }
+}
+
+impl MazeBuilder for Houston {
+    fn name(&self) -> &str {
+        "Houston"
+    }
+
+    fn description(&self) -> &str {
+        "Runs Aldous Broder until a fraction of the cells nearest the start are visited, then switches to Wilson's for the rest. Produces an unbiased maze, faster than Aldous Broder."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_houston_into_grid(grid_map, rng, self.switch_fraction)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_houston_into_grid(grid, rng, self.switch_fraction))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_houston_into_grid(grid, rng, self.switch_fraction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_builders::{carve_aldous_broder_into_grid, carve_wilson_into_grid};
+    use bevy::utils::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Carves many mazes into a 3x3 grid, which has 192 possible mazes, and returns
+    // Pearson's chi-squared statistic for how far the number of times each maze
+    // turned up is from them all turning up equally often.
+    fn get_chi_squared(carve: impl Fn(&GridMap, &mut Rng) -> WallSet) -> f64 {
+        const SAMPLE_COUNT: usize = 100_000;
+        let grid_map = GridMap::new(3, 3);
+        let mut rng = Rng::with_seed(0);
+        let mut counts: HashMap<Vec<[i32; 4]>, usize> = HashMap::new();
+        (0..SAMPLE_COUNT).for_each(|_| {
+            let maze = carve(&grid_map, &mut rng)
+                .iter()
+                .map(|wall| [wall.from.x, wall.from.y, wall.to.x, wall.to.y])
+                .collect();
+            *counts.entry(maze).or_default() += 1;
+        });
+
+        assert_eq!(counts.len(), 192);
+        let expected = SAMPLE_COUNT as f64 / 192.0;
+        counts
+            .values()
+            .map(|count| (*count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    // With 191 degrees of freedom a uniform builder averages 191, and is only
+    // above 300 about once in a million runs.
+    const UNIFORM_CHI_SQUARED_LIMIT: f64 = 300.0;
+
+    #[test]
+    fn aldous_broder_and_wilson_are_uniform() {
+        let aldous_broder = get_chi_squared(carve_aldous_broder_into_grid);
+        let wilson = get_chi_squared(carve_wilson_into_grid);
+        assert!(aldous_broder < UNIFORM_CHI_SQUARED_LIMIT, "{aldous_broder}");
+        assert!(wilson < UNIFORM_CHI_SQUARED_LIMIT, "{wilson}");
+    }
+
+    #[test]
+    fn houston_is_uniform_for_every_switch_fraction() {
+        for switch_fraction in [0.0, 0.25, 0.5, 0.75, 1.0] {
+            let chi_squared = get_chi_squared(|grid_map, rng| {
+                carve_houston_into_grid(grid_map, rng, switch_fraction)
+            });
+            assert!(
+                chi_squared < UNIFORM_CHI_SQUARED_LIMIT,
+                "{switch_fraction}: {chi_squared}"
+            );
+        }
+    }
+
+    // Counts the steps the random walks take, as each step looks up the
+    // neighbours of the cell the walk is in once.
+    struct StepCounter {
+        grid_map: GridMap,
+        steps: AtomicUsize,
+    }
+
+    impl Grid for StepCounter {
+        type Cell = IVec2;
+        type Link = Wall;
+        type LinkSet = WallSet;
+
+        fn cell_count(&self) -> usize {
+            self.grid_map.cell_count()
+        }
+
+        fn cells(&self) -> Box<dyn Iterator<Item = IVec2> + '_> {
+            self.grid_map.cells()
+        }
+
+        fn cell_index(&self, cell: IVec2) -> Option<usize> {
+            self.grid_map.cell_index(cell)
+        }
+
+        fn cell_index_count(&self) -> usize {
+            self.grid_map.cell_index_count()
+        }
+
+        fn random_cell(&self, rng: &mut Rng) -> Option<IVec2> {
+            self.grid_map.random_cell(rng)
+        }
+
+        fn neighbours(&self, cell: IVec2) -> Vec<IVec2> {
+            self.grid_map.neighbours(cell)
+        }
+
+        fn wall_between(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+            self.grid_map.wall_between(from, to)
+        }
+
+        fn new_link_set(&self) -> WallSet {
+            self.grid_map.new_link_set()
+        }
+
+        fn neighbour_walls(&self, cell: IVec2) -> Vec<(IVec2, Wall)> {
+            self.steps.fetch_add(1, Ordering::Relaxed);
+            self.grid_map.neighbour_walls(cell)
+        }
+    }
+
+    // The total number of steps over a few mazes, so one unlucky maze doesn't
+    // decide the result.
+    fn get_step_count(carve: impl Fn(&StepCounter, &mut Rng) -> WallSet) -> usize {
+        let step_counter = StepCounter {
+            grid_map: GridMap::new(40, 40),
+            steps: AtomicUsize::new(0),
+        };
+        let mut rng = Rng::with_seed(0);
+        (0..10).for_each(|_| {
+            carve(&step_counter, &mut rng);
+        });
+        step_counter.steps.into_inner()
+    }
+
+    #[test]
+    fn houston_takes_fewer_steps_than_aldous_broder() {
+        let aldous_broder = get_step_count(carve_aldous_broder_into_grid);
+        let houston = get_step_count(|grid, rng| carve_houston_into_grid(grid, rng, 0.5));
+        assert!(houston < aldous_broder, "{houston} {aldous_broder}");
+    }
+}
@@ -0,0 +1,140 @@
+use super::{CellSet, MazeBuilder};
+use crate::components::Wall;
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+use std::collections::BTreeSet;
+
+pub fn carve_hunt_and_kill_into_grid<G: Grid + ?Sized>(grid: &G, rng: &mut Rng) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    // Unvisited cells that border the maze, by their position in the order the
+    // grid lists its cells, so a hunt finds the first one without going over
+    // the whole grid.
+    let cells: Vec<G::Cell> = grid.cells().collect();
+    let mut positions = vec![0; grid.cell_index_count()];
+    cells.iter().enumerate().for_each(|(position, cell_pos)| {
+        if let Some(index) = grid.cell_index(*cell_pos) {
+            positions[index] = position;
+        }
+    });
+    let mut hunt_candidates = BTreeSet::new();
+    let visit =
+        |visited: &mut CellSet, hunt_candidates: &mut BTreeSet<usize>, cell_pos: G::Cell| {
+            visited.insert(grid, cell_pos);
+            if let Some(index) = grid.cell_index(cell_pos) {
+                hunt_candidates.remove(&positions[index]);
+            }
+            grid.for_each_neighbour_wall(cell_pos, &mut |neighbour, _| {
+                if visited.contains(grid, neighbour) {
+                    return;
+                }
+                if let Some(index) = grid.cell_index(neighbour) {
+                    hunt_candidates.insert(positions[index]);
+                }
+            });
+        };
+
+    let Some(mut current_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visit(&mut visited, &mut hunt_candidates, current_pos);
+
+    let cell_count = grid.cell_count();
+    while visited.len() < cell_count {
+        // The 'kill' phase, a random walk that only steps into unvisited cells.
+        let unvisited_neighbours: Vec<(G::Cell, G::Link)> = grid
+            .neighbour_walls(current_pos)
+            .into_iter()
+            .filter(|(neighbour, _)| !visited.contains(grid, *neighbour))
+            .collect();
+
+        if !unvisited_neighbours.is_empty() {
+            let random_neighbour_index = rng.usize(0..unvisited_neighbours.len());
+            let (neighbour_cell, neighbour_wall) = unvisited_neighbours[random_neighbour_index];
+            removed_walls.insert(neighbour_wall);
+            visit(&mut visited, &mut hunt_candidates, neighbour_cell);
+            current_pos = neighbour_cell;
+            continue;
+        }
+
+        // The 'hunt' phase. The walk is stuck, so find the first unvisited cell
+        // that borders the maze, as a scan over the grid would, and connect it to
+        // the maze. Unlike the recursive backtracker, there's no stack of previous
+        // cells to return to.
+        let Some(hunted_cell) = hunt_candidates.first().map(|position| cells[*position]) else {
+            // Something went wrong, there are unvisited cells but none border the maze.
+            break;
+        };
+
+        let visited_neighbours: Vec<(G::Cell, G::Link)> = grid
+            .neighbour_walls(hunted_cell)
+            .into_iter()
+            .filter(|(neighbour, _)| visited.contains(grid, *neighbour))
+            .collect();
+        let random_neighbour_index = rng.usize(0..visited_neighbours.len());
+        let (_, neighbour_wall) = visited_neighbours[random_neighbour_index];
+        removed_walls.insert(neighbour_wall);
+        visit(&mut visited, &mut hunt_candidates, hunted_cell);
+        current_pos = hunted_cell;
+    }
+
+    removed_walls
+}
+
+pub struct HuntAndKill;
+
+impl MazeBuilder for HuntAndKill {
+    fn name(&self) -> &str {
+        "Hunt and Kill"
+    }
+
+    fn description(&self) -> &str {
+        "A random walk through unvisited cells that, when stuck, hunts for an unvisited cell next to the maze to continue from. Produces long, winding corridors like the recursive backtracker, without needing a stack."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_hunt_and_kill_into_grid(grid_map, rng)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_hunt_and_kill_into_grid(grid, rng))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_hunt_and_kill_into_grid(grid, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::CellMask;
+    use crate::utils::dijkstra;
+
+    // A ring of cells around a hole, so the walk keeps getting stuck and the hunt
+    // has to find its way around the ring.
+    #[test]
+    fn hunting_reaches_every_cell_around_a_hole() {
+        let mut mask = CellMask::new(10, 10);
+        (3..7).for_each(|x| (3..7).for_each(|y| mask.set_enabled(IVec2::new(x, y), false)));
+        let grid_map = GridMap::with_mask(mask).unwrap();
+
+        for seed in 0..10 {
+            let removed_walls = carve_hunt_and_kill_into_grid(&grid_map, &mut Rng::with_seed(seed));
+            let start = grid_map.cells().next().unwrap();
+            let distances = dijkstra(start, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use super::MazeBuilder;
+use crate::components::{Wall, WallOrientation};
+use crate::resources::{
+    Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet, WeaveGrid, WeaveLink,
+};
+use crate::utils::DisjointSet;
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+
+fn get_cell_indices_from_wall(grid_map: &GridMap, wall: Wall) -> Option<(usize, usize)> {
+    let (cell_a, cell_b) = grid_map.cells_from_inner_wall(wall)?;
+    let index_a = grid_map.cell_pos_to_index(cell_a)?;
+    let index_b = grid_map.cell_pos_to_index(cell_b)?;
+    Some((index_a as usize, index_b as usize))
+}
+
+// `seed_passages` are walls removed before the random phase, for example to carve
+// fixed rooms. They're always removed, even if they form loops, and the rest of
+// the maze is carved around them.
+pub fn carve_kruskal_into_grid<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    seed_passages: &[G::Link],
+) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut sets = DisjointSet::new(grid.cell_index_count());
+
+    // Every wall between two cells, once, with the indices of the cells either
+    // side. Outer walls don't have a cell on both sides, so they're never listed.
+    let mut walls: Vec<(usize, usize, G::Link)> = grid
+        .cells()
+        .flat_map(|cell| {
+            let cell_index = grid.cell_index(cell);
+            grid.neighbour_walls(cell)
+                .into_iter()
+                .filter_map(move |(neighbour, wall)| {
+                    let (cell_index, neighbour_index) = (cell_index?, grid.cell_index(neighbour)?);
+                    (cell_index < neighbour_index).then_some((cell_index, neighbour_index, wall))
+                })
+        })
+        .collect();
+
+    let mut seed_walls = grid.new_link_set();
+    seed_walls.extend(seed_passages.iter().copied());
+    walls
+        .iter()
+        .filter(|(_, _, wall)| seed_walls.contains(wall))
+        .for_each(|(cell_a, cell_b, wall)| {
+            sets.union(*cell_a, *cell_b);
+            removed_walls.insert(*wall);
+        });
+
+    rng.shuffle(&mut walls);
+    walls.into_iter().for_each(|(cell_a, cell_b, wall)| {
+        // Only remove walls that join two separate parts of the maze, this
+        // ensures no loops are made.
+        if sets.union(cell_a, cell_b) {
+            removed_walls.insert(wall);
+        }
+    });
+
+    removed_walls
+}
+
+// Tries to put a crossing at a cell, where a passage on top goes straight across
+// the cell, and a tunnel goes under it the other way. None of the cell's walls can
+// have been removed yet, and the cell and its four neighbours have to be in
+// separate parts of the maze so no loops are made.
+fn add_weave_crossing(
+    grid: &WeaveGrid,
+    sets: &mut DisjointSet,
+    removed_links: &mut HashSet<WeaveLink>,
+    cell_pos: IVec2,
+    is_top_vertical: bool,
+) {
+    let (top_directions, tunnel_directions) = match is_top_vertical {
+        true => ([Dir2::NORTH, Dir2::SOUTH], [Dir2::EAST, Dir2::WEST]),
+        false => ([Dir2::EAST, Dir2::WEST], [Dir2::NORTH, Dir2::SOUTH]),
+    };
+    let grid_map = grid.get_grid_map();
+    let is_cell_joined = [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST]
+        .into_iter()
+        .any(|direction| grid.is_wall_removed(cell_pos, direction, removed_links));
+    if is_cell_joined {
+        return;
+    }
+    let Some(cell_index) = grid_map.cell_pos_to_index(cell_pos) else {
+        return;
+    };
+    let Some(sides) = top_directions
+        .into_iter()
+        .chain(tunnel_directions)
+        .map(|direction| {
+            let neighbour_pos = grid_map.neighbour_from_cell_pos(cell_pos, direction)?;
+            let wall = grid_map.inner_wall_from_cell_pos(cell_pos, direction)?;
+            Some((grid_map.cell_pos_to_index(neighbour_pos)? as usize, wall))
+        })
+        .collect::<Option<Vec<(usize, Wall)>>>()
+    else {
+        return;
+    };
+
+    let mut roots: Vec<usize> = std::iter::once(cell_index as usize)
+        .chain(sides.iter().map(|(index, _)| *index))
+        .map(|index| sets.find(index))
+        .collect();
+    roots.sort_unstable();
+    roots.dedup();
+    if roots.len() != 5 {
+        return;
+    }
+
+    sides[0..2].iter().for_each(|(index, wall)| {
+        sets.union(cell_index as usize, *index);
+        removed_links.insert(WeaveLink::Wall(*wall));
+    });
+    sets.union(sides[2].0, sides[3].0);
+    sides[2..4].iter().for_each(|(_, wall)| {
+        removed_links.insert(WeaveLink::Tunnel {
+            wall: *wall,
+            under: cell_pos,
+        });
+    });
+}
+
+// Kruskal's with crossings placed at random cells before the random phase. Walls
+// that a crossing's tunnel goes through are skipped by the random phase.
+pub fn carve_kruskal_into_weave_grid(grid: &WeaveGrid, rng: &mut Rng) -> HashSet<WeaveLink> {
+    let grid_map = grid.get_grid_map();
+    let mut removed_links = HashSet::new();
+    let mut sets = DisjointSet::new(grid_map.get_cell_count() as usize);
+
+    // Crossings need a neighbour on every side, so they're only tried away from
+    // the edges.
+    if grid_map.get_columns() > 2 && grid_map.get_rows() > 2 {
+        (0..grid_map.get_cell_count()).for_each(|_| {
+            let cell_pos = IVec2 {
+                x: rng.i32(1..grid_map.get_columns() - 1),
+                y: rng.i32(1..grid_map.get_rows() - 1),
+            };
+            let is_top_vertical = rng.bool();
+            add_weave_crossing(
+                grid,
+                &mut sets,
+                &mut removed_links,
+                cell_pos,
+                is_top_vertical,
+            );
+        });
+    }
+    let under_cells = grid.get_under_cells(&removed_links);
+
+    let mut walls: Vec<Wall> = grid_map
+        .iter_walls(WallOrientation::Horizontal)
+        .chain(grid_map.iter_walls(WallOrientation::Vertical))
+        .collect();
+    rng.shuffle(&mut walls);
+
+    walls.into_iter().for_each(|wall| {
+        let Some((cell_a, cell_b)) = grid_map.cells_from_inner_wall(wall) else {
+            return;
+        };
+        if under_cells.contains(&cell_a) || under_cells.contains(&cell_b) {
+            return;
+        }
+        if let Some((index_a, index_b)) = get_cell_indices_from_wall(grid_map, wall) {
+            if sets.union(index_a, index_b) {
+                removed_links.insert(WeaveLink::Wall(wall));
+            }
+        }
+    });
+
+    removed_links
+}
+
+#[derive(Default)]
+pub struct Kruskal {
+    pub seed_passages: Vec<Wall>,
+}
+
+impl MazeBuilder for Kruskal {
+    fn name(&self) -> &str {
+        "Kruskal"
+    }
+
+    fn description(&self) -> &str {
+        "Removes walls in a random order, as long as the cells either side aren't already connected. Produces lots of short dead ends."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_kruskal_into_grid(grid_map, rng, &self.seed_passages)
+    }
+
+    // Seed passages that aren't walls of the grid are ignored.
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_kruskal_into_grid(grid, rng, &self.seed_passages))
+    }
+
+    // The seed passages are walls of a grid map, so they aren't used here.
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_kruskal_into_grid(grid, rng, &[]))
+    }
+
+    fn carve_weave_grid(&self, grid: &WeaveGrid, rng: &mut Rng) -> Option<HashSet<WeaveLink>> {
+        Some(carve_kruskal_into_weave_grid(grid, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    fn get_inner_walls(grid_map: &GridMap) -> Vec<Wall> {
+        grid_map
+            .iter_walls(WallOrientation::Horizontal)
+            .chain(grid_map.iter_walls(WallOrientation::Vertical))
+            .filter(|wall| grid_map.cells_from_inner_wall(*wall).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn no_seed_passages_carves_a_perfect_maze() {
+        let grid_map = GridMap::new(12, 9);
+        for seed in 0..5 {
+            let removed_walls = carve_kruskal_into_grid(&grid_map, &mut Rng::with_seed(seed), &[]);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+
+    #[test]
+    fn every_seed_passage_is_removed() {
+        let grid_map = GridMap::new(12, 9);
+        for seed in 0..5 {
+            let mut rng = Rng::with_seed(seed);
+            let seed_passages: Vec<Wall> = get_inner_walls(&grid_map)
+                .into_iter()
+                .filter(|_| rng.f32() < 0.3)
+                .collect();
+
+            let removed_walls = carve_kruskal_into_grid(&grid_map, &mut rng, &seed_passages);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert!(seed_passages
+                .iter()
+                .all(|wall| removed_walls.contains(wall)));
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+
+    // The four walls inside a 2x2 block of cells make a loop, which is kept, so
+    // the maze has one more passage than a perfect maze.
+    #[test]
+    fn a_loop_of_seed_passages_is_kept() {
+        let grid_map = GridMap::new(6, 5);
+        let block = [
+            IVec2::new(2, 2),
+            IVec2::new(3, 2),
+            IVec2::new(3, 3),
+            IVec2::new(2, 3),
+        ];
+        let seed_passages: Vec<Wall> = (0..4)
+            .map(|index| {
+                grid_map
+                    .wall_between(block[index], block[(index + 1) % 4])
+                    .unwrap()
+            })
+            .collect();
+
+        for seed in 0..5 {
+            let removed_walls =
+                carve_kruskal_into_grid(&grid_map, &mut Rng::with_seed(seed), &seed_passages);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert!(seed_passages
+                .iter()
+                .all(|wall| removed_walls.contains(wall)));
+            assert_eq!(removed_walls.len(), grid_map.cell_count());
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+}
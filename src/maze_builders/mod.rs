@@ -1,18 +1,141 @@
 mod aldous_broder;
 mod binary_tree;
+mod ellers;
+mod growing_tree;
+mod houston;
+mod hunt_and_kill;
+mod kruskal;
+mod origin_shift;
+mod prims;
+mod recursive_backtracker;
+mod recursive_division;
 mod sidewinder;
 mod wilson;
 
 pub use aldous_broder::*;
 pub use binary_tree::*;
+pub use ellers::*;
+pub use growing_tree::*;
+pub use houston::*;
+pub use hunt_and_kill::*;
+pub use kruskal::*;
+pub use origin_shift::*;
+pub use prims::*;
+pub use recursive_backtracker::*;
+pub use recursive_division::*;
 pub use sidewinder::*;
 pub use wilson::*;
 
-use bevy::prelude::*;
+use crate::components::Wall;
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, WallSet, WeaveGrid, WeaveLink};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
 
-fn get_direction_from_coinflip(coinflip: bool) -> Dir2 {
+// A maze generation algorithm. Builders are registered with the
+// `MazeBuilderRegistry` resource, which the HUD's 'Maze type' dropdown is built from,
+// so builders defined outside this crate can be selected in the app too.
+pub trait MazeBuilder: Send + Sync {
+    // The name shown in the HUD, also used to identify the builder in the registry.
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str;
+
+    // Returns the walls to remove from a fully walled grid map.
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet;
+
+    // Returns the walls to remove from any other shape of grid. Builders that work
+    // along the rows and columns of a square grid can't do this, and return None.
+    fn carve_grid(
+        &self,
+        _grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        _rng: &mut Rng,
+    ) -> Option<WallSet> {
+        None
+    }
+
+    // Returns the links to remove from a grid of several levels, including the
+    // floors between them. Only builders that can carve any shape of grid can do
+    // this, the others return None.
+    fn carve_layered_grid(
+        &self,
+        _grid: &LayeredGrid,
+        _rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        None
+    }
+
+    // Returns the links to remove from a grid where passages can tunnel under
+    // each other. Builders need to know which cells can be tunnelled under, so
+    // most can't do this and return None.
+    fn carve_weave_grid(&self, _grid: &WeaveGrid, _rng: &mut Rng) -> Option<HashSet<WeaveLink>> {
+        None
+    }
+}
+
+// Builders that work along rows or columns can't carve around cells that are
+// turned off, so they can leave regions cut off from the rest of the maze. This
+// joins them back up with Kruskal's, keeping the walls that were already removed.
+fn join_masked_regions(grid_map: &GridMap, rng: &mut Rng, removed_walls: WallSet) -> WallSet {
+    if grid_map.get_mask().is_none() {
+        return removed_walls;
+    }
+
+    let seed_passages: Vec<Wall> = removed_walls.iter().collect();
+    carve_kruskal_into_grid(grid_map, rng, &seed_passages)
+}
+
+fn get_direction_from_coinflip(coinflip: bool, directions: (Dir2, Dir2)) -> Dir2 {
     match coinflip {
-        true => Dir2::NORTH,
-        false => Dir2::EAST,
+        true => directions.0,
+        false => directions.1,
+    }
+}
+
+// A set of a grid's cells, like the ones a builder has visited, kept by
+// `Grid::cell_index` rather than hashed, as checking whether a cell's been visited
+// is most of what builders do.
+struct CellSet {
+    by_index: Vec<bool>,
+    len: usize,
+}
+
+impl CellSet {
+    fn new<G: Grid + ?Sized>(grid: &G) -> Self {
+        CellSet {
+            by_index: vec![false; grid.cell_index_count()],
+            len: 0,
+        }
+    }
+
+    // Returns whether the cell wasn't already in the set. Cells without an index
+    // can't be added.
+    fn insert<G: Grid + ?Sized>(&mut self, grid: &G, cell: G::Cell) -> bool {
+        let Some(is_visited) = grid
+            .cell_index(cell)
+            .and_then(|index| self.by_index.get_mut(index))
+        else {
+            return false;
+        };
+        if *is_visited {
+            return false;
+        }
+
+        *is_visited = true;
+        self.len += 1;
+        true
+    }
+
+    fn contains<G: Grid + ?Sized>(&self, grid: &G, cell: G::Cell) -> bool {
+        grid.cell_index(cell)
+            .and_then(|index| self.by_index.get(index).copied())
+            .unwrap_or(false)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
     }
 }
@@ -0,0 +1,227 @@
+use super::{CellSet, MazeBuilder};
+use crate::components::{Wall, WallOrientation};
+use crate::resources::{Grid, GridMap, WallSet};
+use bevy::prelude::*;
+use fastrand::Rng;
+use std::collections::VecDeque;
+
+// A perfect maze stored as a tree where every cell points towards the 'origin'
+// cell. Shifting the origin to a random neighbour changes the maze by one
+// passage while keeping it a perfect maze, so it can be mutated step by step.
+pub struct OriginShiftMaze {
+    origin: IVec2,
+    // For each cell index, the next cell on the way to the origin.
+    next: Vec<Option<IVec2>>,
+}
+
+impl OriginShiftMaze {
+    // A simple starting maze where every row runs east into the last column, which
+    // runs north to the origin in the north east corner.
+    pub fn new(grid_map: &GridMap) -> Self {
+        if grid_map.get_mask().is_some() {
+            // That simple maze can't go around cells that are turned off, so
+            // instead search outwards through every inner wall.
+            let mut inner_walls = grid_map.new_link_set();
+            inner_walls.extend(
+                grid_map
+                    .iter_walls(WallOrientation::Horizontal)
+                    .chain(grid_map.iter_walls(WallOrientation::Vertical))
+                    .filter(|wall| grid_map.cells_from_inner_wall(*wall).is_some()),
+            );
+            let origin = grid_map
+                .iter_cells()
+                .last()
+                .unwrap_or(grid_map.get_north_east_cell_pos());
+            return Self::from_removed_walls(grid_map, &inner_walls, origin);
+        }
+
+        let origin = grid_map.get_north_east_cell_pos();
+        let next = grid_map
+            .iter_cells()
+            .map(|cell_pos| {
+                if cell_pos == origin {
+                    None
+                } else if cell_pos.x == origin.x {
+                    Some(cell_pos + IVec2::Y)
+                } else {
+                    Some(cell_pos + IVec2::X)
+                }
+            })
+            .collect();
+
+        OriginShiftMaze { origin, next }
+    }
+
+    // Builds the tree from an existing maze by searching outwards from `origin`.
+    // If the maze has loops, only the passages the search travels along are kept.
+    pub fn from_removed_walls(grid_map: &GridMap, removed_walls: &WallSet, origin: IVec2) -> Self {
+        let mut next = vec![None; grid_map.get_cell_count() as usize];
+        let mut visited = CellSet::new(grid_map);
+        visited.insert(grid_map, origin);
+        let mut frontier = VecDeque::from([origin]);
+
+        while let Some(cell_pos) = frontier.pop_front() {
+            [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST]
+                .into_iter()
+                .for_each(|direction| {
+                    let neighbour = grid_map.neighbour_from_cell_pos(cell_pos, direction);
+                    let wall = grid_map.inner_wall_from_cell_pos(cell_pos, direction);
+                    if let (Some(neighbour), Some(wall)) = (neighbour, wall) {
+                        if removed_walls.contains(&wall) && visited.insert(grid_map, neighbour) {
+                            let index = grid_map.cell_pos_to_index(neighbour).unwrap();
+                            next[index as usize] = Some(cell_pos);
+                            frontier.push_back(neighbour);
+                        }
+                    }
+                });
+        }
+
+        OriginShiftMaze { origin, next }
+    }
+
+    pub fn get_origin(&self) -> IVec2 {
+        self.origin
+    }
+
+    // Returns whether a cell is part of the tree, cells that couldn't be reached
+    // when building it from existing walls aren't.
+    fn contains(&self, grid_map: &GridMap, cell_pos: IVec2) -> bool {
+        cell_pos == self.origin
+            || grid_map
+                .cell_pos_to_index(cell_pos)
+                .is_some_and(|index| self.next[index as usize].is_some())
+    }
+
+    // Moves the origin to a random neighbour. Returns the wall that's removed, and
+    // the wall that's put back, which are the same wall if the passage between the
+    // old and new origin already existed.
+    pub fn shift(&mut self, grid_map: &GridMap, rng: &mut Rng) -> Option<(Wall, Wall)> {
+        let neighbours: Vec<IVec2> = [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST]
+            .into_iter()
+            .filter_map(|direction| grid_map.neighbour_from_cell_pos(self.origin, direction))
+            .filter(|neighbour| self.contains(grid_map, *neighbour))
+            .collect();
+        if neighbours.is_empty() {
+            return None;
+        }
+
+        let new_origin = neighbours[rng.usize(0..neighbours.len())];
+        let origin_index = grid_map.cell_pos_to_index(self.origin)? as usize;
+        let new_origin_index = grid_map.cell_pos_to_index(new_origin)? as usize;
+
+        let removed_wall = grid_map.inner_wall_between_cell_pos(self.origin, new_origin)?;
+        let new_origin_next = self.next[new_origin_index]?;
+        let restored_wall = grid_map.inner_wall_between_cell_pos(new_origin, new_origin_next)?;
+
+        self.next[origin_index] = Some(new_origin);
+        self.next[new_origin_index] = None;
+        self.origin = new_origin;
+
+        Some((removed_wall, restored_wall))
+    }
+
+    pub fn get_removed_walls(&self, grid_map: &GridMap) -> WallSet {
+        let mut removed_walls = grid_map.new_link_set();
+        removed_walls.extend(self.next.iter().enumerate().filter_map(|(index, next)| {
+            let cell_pos = grid_map.index_to_cell_pos(index as i32)?;
+            grid_map.inner_wall_between_cell_pos(cell_pos, (*next)?)
+        }));
+        removed_walls
+    }
+}
+
+pub fn carve_origin_shift_into_grid_map(
+    grid_map: &GridMap,
+    rng: &mut Rng,
+    shift_count: usize,
+) -> WallSet {
+    let mut maze = OriginShiftMaze::new(grid_map);
+    for _ in 0..shift_count {
+        maze.shift(grid_map, rng);
+    }
+
+    maze.get_removed_walls(grid_map)
+}
+
+pub struct OriginShift {
+    // The number of times the origin is shifted, per cell in the grid map.
+    // More shifts leave less of the simple starting maze behind.
+    pub shifts_per_cell: usize,
+}
+
+impl Default for OriginShift {
+    fn default() -> Self {
+        OriginShift {
+            shifts_per_cell: 10,
+        }
+    }
+}
+
+impl MazeBuilder for OriginShift {
+    fn name(&self) -> &str {
+        "Origin Shift"
+    }
+
+    fn description(&self) -> &str {
+        "Starts from a simple maze where every cell points towards an origin cell, then repeatedly moves the origin to a random neighbour. Every step keeps it a perfect maze, so it can also slowly change a maze over time."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        let shift_count = grid_map.get_enabled_cell_count() as usize * self.shifts_per_cell;
+        carve_origin_shift_into_grid_map(grid_map, rng, shift_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::CellMask;
+    use crate::utils::dijkstra;
+
+    fn assert_spanning_tree(grid_map: &GridMap, removed_walls: &WallSet) {
+        let start = grid_map.iter_cells().next().unwrap();
+        let distances = dijkstra(start, grid_map, removed_walls);
+        assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+        assert_eq!(distances.len(), grid_map.cell_count());
+    }
+
+    #[test]
+    fn shifting_moves_the_origin_and_keeps_a_spanning_tree() {
+        let mut mask = CellMask::new(8, 6);
+        mask.set_enabled(IVec2::new(3, 2), false);
+        let masked_grid_map = GridMap::with_mask(mask).unwrap();
+
+        for grid_map in [GridMap::new(8, 6), masked_grid_map] {
+            let mut rng = Rng::with_seed(0);
+            let mut maze = OriginShiftMaze::new(&grid_map);
+            // The walls are kept up to date a shift at a time, like the living maze.
+            let mut removed_walls = maze.get_removed_walls(&grid_map);
+            assert_spanning_tree(&grid_map, &removed_walls);
+
+            for _ in 0..500 {
+                let origin = maze.get_origin();
+                let (removed_wall, restored_wall) = maze.shift(&grid_map, &mut rng).unwrap();
+                let new_origin = maze.get_origin();
+                assert_ne!(origin, new_origin);
+                assert!(
+                    grid_map.inner_wall_between_cell_pos(origin, new_origin) == Some(removed_wall)
+                );
+
+                removed_walls.remove(&restored_wall);
+                removed_walls.insert(removed_wall);
+                assert!(removed_walls == maze.get_removed_walls(&grid_map));
+                assert_spanning_tree(&grid_map, &removed_walls);
+            }
+        }
+    }
+
+    #[test]
+    fn rebuilding_from_walls_keeps_the_maze() {
+        let grid_map = GridMap::new(10, 7);
+        let removed_walls =
+            carve_origin_shift_into_grid_map(&grid_map, &mut Rng::with_seed(1), 700);
+        let maze = OriginShiftMaze::from_removed_walls(&grid_map, &removed_walls, IVec2::new(4, 4));
+        assert_eq!(maze.get_origin(), IVec2::new(4, 4));
+        assert!(maze.get_removed_walls(&grid_map) == removed_walls);
+    }
+}
@@ -0,0 +1,173 @@
+use super::{CellSet, MazeBuilder};
+use crate::components::Wall;
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+const MAX_CELL_COST: u32 = 100;
+
+fn get_unvisited_neighbours<G: Grid + ?Sized>(
+    grid: &G,
+    cell_pos: G::Cell,
+    visited: &CellSet,
+) -> Vec<(G::Cell, G::Link)> {
+    grid.neighbour_walls(cell_pos)
+        .into_iter()
+        .filter(|(neighbour, _)| !visited.contains(grid, *neighbour))
+        .collect()
+}
+
+// Grows the maze from a random cell by repeatedly picking a random 'active' cell
+// on the edge of the maze and carving into one of its unvisited neighbours.
+pub fn carve_simplified_prims_into_grid<G: Grid + ?Sized>(grid: &G, rng: &mut Rng) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    let Some(start_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visited.insert(grid, start_pos);
+    let mut active = vec![start_pos];
+
+    while !active.is_empty() {
+        let active_index = rng.usize(0..active.len());
+        let active_cell = active[active_index];
+        let unvisited_neighbours = get_unvisited_neighbours(grid, active_cell, &visited);
+
+        // Cells with no unvisited neighbours can't grow the maze any further.
+        if unvisited_neighbours.is_empty() {
+            active.swap_remove(active_index);
+            continue;
+        }
+
+        let random_neighbour_index = rng.usize(0..unvisited_neighbours.len());
+        let (neighbour_cell, neighbour_wall) = unvisited_neighbours[random_neighbour_index];
+        removed_walls.insert(neighbour_wall);
+        visited.insert(grid, neighbour_cell);
+        active.push(neighbour_cell);
+    }
+
+    removed_walls
+}
+
+// Gives every cell a random cost, and always grows the maze from the cheapest
+// active cell into its cheapest unvisited neighbour.
+pub fn carve_true_prims_into_grid<G: Grid + ?Sized>(grid: &G, rng: &mut Rng) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    // Costs are kept by cell index, and picked in the order the grid lists its
+    // cells. The cells are kept by index too, so the active cells can be ordered
+    // by their index without the cells themselves needing an order.
+    let mut cells = vec![None; grid.cell_index_count()];
+    let mut costs = vec![0; grid.cell_index_count()];
+    grid.cells().for_each(|cell_pos| {
+        let cost = rng.u32(0..MAX_CELL_COST);
+        if let Some(index) = grid.cell_index(cell_pos) {
+            cells[index] = Some(cell_pos);
+            costs[index] = cost;
+        }
+    });
+    let get_cost_and_index = |cell_pos: G::Cell| -> Option<(u32, usize)> {
+        let index = grid.cell_index(cell_pos)?;
+        Some((costs[index], index))
+    };
+
+    // Ties in cost are broken by the cell index, which keeps the result
+    // reproducible for a given seed.
+    let Some(start_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visited.insert(grid, start_pos);
+    let mut active = BinaryHeap::new();
+    active.extend(get_cost_and_index(start_pos).map(Reverse));
+
+    while let Some(Reverse((_, active_index))) = active.peek().copied() {
+        let Some(active_cell) = cells[active_index] else {
+            active.pop();
+            continue;
+        };
+        let unvisited_neighbours = get_unvisited_neighbours(grid, active_cell, &visited);
+
+        let cheapest_neighbour = unvisited_neighbours
+            .into_iter()
+            .min_by_key(|(neighbour, _)| get_cost_and_index(*neighbour));
+
+        // Cells with no unvisited neighbours can't grow the maze any further.
+        let Some((neighbour_cell, neighbour_wall)) = cheapest_neighbour else {
+            active.pop();
+            continue;
+        };
+
+        removed_walls.insert(neighbour_wall);
+        visited.insert(grid, neighbour_cell);
+        active.extend(get_cost_and_index(neighbour_cell).map(Reverse));
+    }
+
+    removed_walls
+}
+
+pub struct SimplifiedPrims;
+
+impl MazeBuilder for SimplifiedPrims {
+    fn name(&self) -> &str {
+        "Simplified Prim's"
+    }
+
+    fn description(&self) -> &str {
+        "Grows the maze outwards from a random cell by carving from random cells on its edge. Produces a radial texture with lots of short dead ends."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_simplified_prims_into_grid(grid_map, rng)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_simplified_prims_into_grid(grid, rng))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_simplified_prims_into_grid(grid, rng))
+    }
+}
+
+pub struct TruePrims;
+
+impl MazeBuilder for TruePrims {
+    fn name(&self) -> &str {
+        "True Prim's"
+    }
+
+    fn description(&self) -> &str {
+        "Gives each cell a random cost and grows the maze outwards from a random cell, always carving into the cheapest cell on its edge. Produces a radial texture with many short dead ends."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_true_prims_into_grid(grid_map, rng)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_true_prims_into_grid(grid, rng))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_true_prims_into_grid(grid, rng))
+    }
+}
@@ -0,0 +1,185 @@
+use super::{CellSet, MazeBuilder};
+use crate::components::Wall;
+use crate::resources::{
+    Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet, WeaveGrid, WeaveLink,
+};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+
+#[deprecated(note = "use `carve_recursive_backtracker_into_grid`, which carves any `Grid`")]
+pub fn carve_recursive_backtracker_into_grid_map(grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+    carve_recursive_backtracker_into_grid(grid_map, rng)
+}
+
+pub fn carve_recursive_backtracker_into_grid<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
+
+    let Some(start_pos) = grid.random_cell(rng) else {
+        return removed_walls;
+    };
+    visited.insert(grid, start_pos);
+
+    // The cells on the current path are kept on an explicit stack rather than
+    // recursing, so that large grid maps can't overflow the call stack.
+    let mut stack = vec![start_pos];
+    let mut unvisited_neighbours = Vec::new();
+
+    while let Some(current_pos) = stack.last().copied() {
+        unvisited_neighbours.clear();
+        grid.for_each_neighbour_wall(current_pos, &mut |neighbour, wall| {
+            if !visited.contains(grid, neighbour) {
+                unvisited_neighbours.push((neighbour, wall));
+            }
+        });
+
+        // At a dead end, backtrack along the path until there's a cell with
+        // unvisited neighbours.
+        if unvisited_neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let random_neighbour_index = rng.usize(0..unvisited_neighbours.len());
+        let (neighbour_cell, neighbour_wall) = unvisited_neighbours[random_neighbour_index];
+        removed_walls.insert(neighbour_wall);
+        visited.insert(grid, neighbour_cell);
+        stack.push(neighbour_cell);
+    }
+
+    removed_walls
+}
+
+// Like `carve_recursive_backtracker_into_grid`, but when a neighbour has already
+// been visited the walk can tunnel under it to the cell beyond, if the neighbour
+// is a straight corridor going across the way.
+pub fn carve_recursive_backtracker_into_weave_grid(
+    grid: &WeaveGrid,
+    rng: &mut Rng,
+) -> HashSet<WeaveLink> {
+    let grid_map = grid.get_grid_map();
+    let mut removed_links = HashSet::new();
+    let mut visited = CellSet::new(grid_map);
+
+    let Some(start_pos) = grid_map.random_cell_pos(rng) else {
+        return removed_links;
+    };
+    visited.insert(grid_map, start_pos);
+
+    let mut stack = vec![start_pos];
+
+    while let Some(current_pos) = stack.last().copied() {
+        let unvisited_neighbours: Vec<(IVec2, Vec<WeaveLink>)> =
+            [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST]
+                .into_iter()
+                .filter_map(|direction| {
+                    let neighbour_pos = grid_map.neighbour_from_cell_pos(current_pos, direction)?;
+                    let wall = grid_map.inner_wall_from_cell_pos(current_pos, direction)?;
+                    if !visited.contains(grid_map, neighbour_pos) {
+                        return Some((neighbour_pos, vec![WeaveLink::Wall(wall)]));
+                    }
+
+                    if !grid.can_tunnel_under(neighbour_pos, direction, &removed_links) {
+                        return None;
+                    }
+                    let far_pos = grid_map.neighbour_from_cell_pos(neighbour_pos, direction)?;
+                    let far_wall = grid_map.inner_wall_from_cell_pos(neighbour_pos, direction)?;
+                    if visited.contains(grid_map, far_pos) {
+                        return None;
+                    }
+                    let tunnel = vec![
+                        WeaveLink::Tunnel {
+                            wall,
+                            under: neighbour_pos,
+                        },
+                        WeaveLink::Tunnel {
+                            wall: far_wall,
+                            under: neighbour_pos,
+                        },
+                    ];
+                    Some((far_pos, tunnel))
+                })
+                .collect();
+
+        if unvisited_neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let random_neighbour_index = rng.usize(0..unvisited_neighbours.len());
+        let (neighbour_pos, links) = &unvisited_neighbours[random_neighbour_index];
+        removed_links.extend(links.iter().copied());
+        visited.insert(grid_map, *neighbour_pos);
+        stack.push(*neighbour_pos);
+    }
+
+    removed_links
+}
+
+pub struct RecursiveBacktracker;
+
+impl MazeBuilder for RecursiveBacktracker {
+    fn name(&self) -> &str {
+        "Recursive Backtracker"
+    }
+
+    fn description(&self) -> &str {
+        "A random walk that only steps into unvisited cells, backtracking when it gets stuck. Produces long, winding corridors with few dead ends."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_recursive_backtracker_into_grid(grid_map, rng)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_recursive_backtracker_into_grid(grid, rng))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_recursive_backtracker_into_grid(grid, rng))
+    }
+
+    fn carve_weave_grid(&self, grid: &WeaveGrid, rng: &mut Rng) -> Option<HashSet<WeaveLink>> {
+        Some(carve_recursive_backtracker_into_weave_grid(grid, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    #[test]
+    #[allow(deprecated)]
+    fn the_grid_map_alias_carves_the_same_maze() {
+        let grid_map = GridMap::new(16, 12);
+        let removed_walls =
+            carve_recursive_backtracker_into_grid(&grid_map, &mut Rng::with_seed(3));
+        let aliased_walls =
+            carve_recursive_backtracker_into_grid_map(&grid_map, &mut Rng::with_seed(3));
+        assert!(removed_walls == aliased_walls);
+    }
+
+    // The path back to the start can be as long as the maze, which would overflow
+    // the call stack if the builder recursed.
+    #[test]
+    fn carves_large_grid_maps() {
+        let grid_map = GridMap::new(300, 300);
+        let removed_walls =
+            carve_recursive_backtracker_into_grid(&grid_map, &mut Rng::with_seed(0));
+        let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+        assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+        assert_eq!(distances.len(), grid_map.cell_count());
+    }
+}
@@ -0,0 +1,211 @@
+use super::{join_masked_regions, MazeBuilder};
+use crate::components::{Wall, WallOrientation};
+use crate::resources::{Grid, GridMap, WallSet};
+use bevy::prelude::*;
+use fastrand::Rng;
+
+// An area of the grid map that's still to be divided, the position is its
+// south-west cell.
+struct Region {
+    position: IVec2,
+    size: IVec2,
+}
+
+// Unlike the other builders, this starts from a grid map with no inner walls and
+// adds walls back in. The result is still the set of removed walls.
+//
+// Regions that are narrower and shorter than `room_size` cells are left
+// undivided as open rooms. A `room_size` of 0 produces a perfect maze.
+pub fn carve_recursive_division_into_grid_map(
+    grid_map: &GridMap,
+    rng: &mut Rng,
+    room_size: i32,
+) -> WallSet {
+    let mut removed_walls = grid_map.new_link_set();
+    removed_walls.extend(
+        grid_map
+            .iter_walls(WallOrientation::Horizontal)
+            .chain(grid_map.iter_walls(WallOrientation::Vertical))
+            .filter(|wall| grid_map.cells_from_inner_wall(*wall).is_some())
+            // Divisions never cross a seam, so seams stay closed rather than being
+            // left open for good.
+            .filter(|wall| !grid_map.is_seam_wall(*wall)),
+    );
+
+    // Regions are kept on an explicit stack rather than recursing, so that large
+    // grid maps can't overflow the call stack.
+    let mut regions = vec![Region {
+        position: IVec2::ZERO,
+        size: IVec2 {
+            x: grid_map.get_columns(),
+            y: grid_map.get_rows(),
+        },
+    }];
+
+    while let Some(Region { position, size }) = regions.pop() {
+        let is_room = size.x < room_size && size.y < room_size;
+        if size.x <= 1 || size.y <= 1 || is_room {
+            continue;
+        }
+
+        // Divide across the longest side, so that regions don't become long and thin.
+        let is_horizontal = match size.y.cmp(&size.x) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less => false,
+            std::cmp::Ordering::Equal => rng.bool(),
+        };
+
+        if is_horizontal {
+            let divide_at = rng.i32(1..size.y);
+            let passage_at = rng.i32(0..size.x);
+            (0..size.x).filter(|x| *x != passage_at).for_each(|x| {
+                let from = position + IVec2 { x, y: divide_at };
+                removed_walls.remove(&Wall {
+                    from,
+                    to: IVec2 {
+                        x: from.x + 1,
+                        y: from.y,
+                    },
+                });
+            });
+
+            regions.push(Region {
+                position,
+                size: IVec2 {
+                    x: size.x,
+                    y: divide_at,
+                },
+            });
+            regions.push(Region {
+                position: position + IVec2 { x: 0, y: divide_at },
+                size: IVec2 {
+                    x: size.x,
+                    y: size.y - divide_at,
+                },
+            });
+        } else {
+            let divide_at = rng.i32(1..size.x);
+            let passage_at = rng.i32(0..size.y);
+            (0..size.y).filter(|y| *y != passage_at).for_each(|y| {
+                let from = position + IVec2 { x: divide_at, y };
+                removed_walls.remove(&Wall {
+                    from,
+                    to: IVec2 {
+                        x: from.x,
+                        y: from.y + 1,
+                    },
+                });
+            });
+
+            regions.push(Region {
+                position,
+                size: IVec2 {
+                    x: divide_at,
+                    y: size.y,
+                },
+            });
+            regions.push(Region {
+                position: position + IVec2 { x: divide_at, y: 0 },
+                size: IVec2 {
+                    x: size.x - divide_at,
+                    y: size.y,
+                },
+            });
+        }
+    }
+
+    // A division's passage can land on a cell that's turned off.
+    join_masked_regions(grid_map, rng, removed_walls)
+}
+
+#[derive(Default)]
+pub struct RecursiveDivision {
+    pub room_size: i32,
+}
+
+impl MazeBuilder for RecursiveDivision {
+    fn name(&self) -> &str {
+        "Recursive Division"
+    }
+
+    fn description(&self) -> &str {
+        "Starts with no inner walls and repeatedly divides the grid with a wall that has a single gap. Produces long, straight walls, and can leave open rooms."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_recursive_division_into_grid_map(grid_map, rng, self.room_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    fn get_inner_walls(grid_map: &GridMap) -> Vec<Wall> {
+        grid_map
+            .iter_walls(WallOrientation::Horizontal)
+            .chain(grid_map.iter_walls(WallOrientation::Vertical))
+            .filter(|wall| grid_map.cells_from_inner_wall(*wall).is_some())
+            .collect()
+    }
+
+    // Returns whether all four walls inside the 2x2 block of cells with its
+    // south-west cell at `cell_pos` are removed.
+    fn is_open_block(grid_map: &GridMap, removed_walls: &WallSet, cell_pos: IVec2) -> bool {
+        [
+            (IVec2::ZERO, IVec2::X),
+            (IVec2::ZERO, IVec2::Y),
+            (IVec2::X, IVec2::ONE),
+            (IVec2::Y, IVec2::ONE),
+        ]
+        .into_iter()
+        .all(|(from, to)| {
+            let wall = grid_map.wall_between(cell_pos + from, cell_pos + to);
+            wall.is_some_and(|wall| removed_walls.contains(&wall))
+        })
+    }
+
+    #[test]
+    fn no_rooms_carves_a_perfect_maze() {
+        let grid_map = GridMap::new(16, 12);
+        for seed in 0..5 {
+            let removed_walls =
+                carve_recursive_division_into_grid_map(&grid_map, &mut Rng::with_seed(seed), 0);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+        }
+    }
+
+    #[test]
+    fn grid_maps_smaller_than_a_room_are_left_open() {
+        let grid_map = GridMap::new(4, 3);
+        let removed_walls =
+            carve_recursive_division_into_grid_map(&grid_map, &mut Rng::with_seed(0), 5);
+        let inner_walls = get_inner_walls(&grid_map);
+        assert_eq!(removed_walls.len(), inner_walls.len());
+        assert!(inner_walls.iter().all(|wall| removed_walls.contains(wall)));
+    }
+
+    #[test]
+    fn rooms_are_left_open_and_joined_to_the_rest_of_the_maze() {
+        let grid_map = GridMap::new(16, 12);
+        for seed in 0..5 {
+            let removed_walls =
+                carve_recursive_division_into_grid_map(&grid_map, &mut Rng::with_seed(seed), 4);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(distances.len(), grid_map.cell_count());
+
+            // Rooms have loops in them, so there are more passages than a perfect
+            // maze, but the walls between the rooms are still there.
+            assert!(removed_walls.len() > grid_map.cell_count() - 1);
+            assert!(removed_walls.len() < get_inner_walls(&grid_map).len());
+            assert!(grid_map.iter_cells().any(|cell_pos| is_open_block(
+                &grid_map,
+                &removed_walls,
+                cell_pos
+            )));
+        }
+    }
+}
@@ -1,40 +1,90 @@
-use super::get_direction_from_coinflip;
-use crate::components::Wall;
-use crate::resources::GridMap;
-use bevy::{prelude::*, utils::HashSet};
+use super::{get_direction_from_coinflip, join_masked_regions, MazeBuilder};
+use crate::resources::{Grid, GridMap, WallSet};
+use bevy::prelude::*;
 use fastrand::Rng;
 
-pub fn carve_sidewinder_into_grid_map(grid_map: &GridMap, rng_seed: u64) -> HashSet<Wall> {
-    let mut rng = Rng::with_seed(rng_seed);
-    let mut removed_walls = HashSet::new();
+#[derive(Copy, Clone, PartialEq)]
+pub enum RunOrientation {
+    // Runs are carved east along each row, and closed by carving north.
+    Horizontal,
+    // Runs are carved north along each column, and closed by carving east.
+    Vertical,
+}
+
+impl RunOrientation {
+    // Returns the (close, run) directions.
+    pub fn get_directions(&self) -> (Dir2, Dir2) {
+        match self {
+            RunOrientation::Horizontal => (Dir2::NORTH, Dir2::EAST),
+            RunOrientation::Vertical => (Dir2::EAST, Dir2::NORTH),
+        }
+    }
+}
+
+#[derive(Resource, Copy, Clone, PartialEq)]
+pub struct SidewinderBias {
+    pub run_orientation: RunOrientation,
+    // The chance of continuing the current run rather than closing it.
+    pub run_probability: f32,
+}
+
+impl Default for SidewinderBias {
+    fn default() -> Self {
+        SidewinderBias {
+            run_orientation: RunOrientation::Horizontal,
+            run_probability: 0.5,
+        }
+    }
+}
+
+pub fn carve_sidewinder_into_grid_map(
+    grid_map: &GridMap,
+    rng: &mut Rng,
+    bias: SidewinderBias,
+) -> WallSet {
+    let mut removed_walls = grid_map.new_link_set();
     let mut run = Vec::new();
+    let directions = bias.run_orientation.get_directions();
+    let (close_direction, run_direction) = directions;
+    // Runs end at the edges of the grid map even when it wraps around, otherwise
+    // a run could carry on around the whole row.
+    let unwrapped_grid_map = grid_map.without_wrapping();
+
+    // Cells are visited along the runs, so vertical runs go column by column.
+    let cells: Vec<IVec2> = match bias.run_orientation {
+        RunOrientation::Horizontal => grid_map.iter_cells().collect(),
+        RunOrientation::Vertical => (0..grid_map.get_columns())
+            .flat_map(|x| (0..grid_map.get_rows()).map(move |y| IVec2 { x, y }))
+            .filter(|cell_pos| grid_map.is_cell_enabled(*cell_pos))
+            .collect(),
+    };
 
-    grid_map.iter_cells().for_each(|cell_pos| {
+    cells.into_iter().for_each(|cell_pos| {
         run.push(cell_pos);
 
-        let coinflip = rng.bool();
-        let mut direction = get_direction_from_coinflip(coinflip);
+        let coinflip = rng.f32() >= bias.run_probability;
+        let mut direction = get_direction_from_coinflip(coinflip, directions);
 
-        let mut wall = grid_map.inner_wall_from_cell_pos(cell_pos, direction);
+        let mut wall = unwrapped_grid_map.inner_wall_from_cell_pos(cell_pos, direction);
 
         // If we've selected a wall at the boundary then flip the direction.
         if wall.is_none() {
-            direction = get_direction_from_coinflip(!coinflip);
+            direction = get_direction_from_coinflip(!coinflip, directions);
         }
 
-        // If we're traveling east, continue carving in that direction building up a
-        // 'run' of cells until we hit the boundary, at which point we switch direction
-        // to north.
-        if direction == Dir2::EAST {
-            wall = grid_map.inner_wall_from_cell_pos(cell_pos, direction);
+        // If we're continuing the run, carve in that direction building up a
+        // 'run' of cells until we hit the boundary, at which point we switch
+        // direction to close the run.
+        if direction == run_direction {
+            wall = unwrapped_grid_map.inner_wall_from_cell_pos(cell_pos, direction);
         }
 
-        // When travelling north, pick a random cell to carve north from one of
-        // the stored 'run' cells.
-        if direction == Dir2::NORTH {
+        // When closing the run, pick a random cell to carve from out of the
+        // stored 'run' cells.
+        if direction == close_direction {
             let run_index = rng.usize(0..run.len());
             let run_cell = run[run_index];
-            wall = grid_map.inner_wall_from_cell_pos(run_cell, direction);
+            wall = unwrapped_grid_map.inner_wall_from_cell_pos(run_cell, direction);
             run.clear();
         }
 
@@ -44,5 +94,114 @@ pub fn carve_sidewinder_into_grid_map(grid_map: &GridMap, rng_seed: u64) -> Hash
         }
     });
 
-    removed_walls
+    join_masked_regions(grid_map, rng, removed_walls)
+}
+
+#[derive(Default)]
+pub struct Sidewinder {
+    pub bias: SidewinderBias,
+}
+
+impl MazeBuilder for Sidewinder {
+    fn name(&self) -> &str {
+        "Sidewinder"
+    }
+
+    fn description(&self) -> &str {
+        "Carves runs of cells along each row or column, then closes each run by carving out of a random cell in it. Leaves a long corridor along one edge."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_sidewinder_into_grid_map(grid_map, rng, self.bias)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    fn is_wall_removed(
+        grid_map: &GridMap,
+        removed_walls: &WallSet,
+        cell_pos: IVec2,
+        direction: Dir2,
+    ) -> bool {
+        grid_map
+            .inner_wall_from_cell_pos(cell_pos, direction)
+            .is_some_and(|wall| removed_walls.contains(&wall))
+    }
+
+    // The last row or column along the close direction can't be closed, so it's
+    // one long run.
+    #[test]
+    fn the_far_edge_is_one_open_corridor_for_each_orientation() {
+        let grid_map = GridMap::new(10, 8);
+        for run_orientation in [RunOrientation::Horizontal, RunOrientation::Vertical] {
+            let bias = SidewinderBias {
+                run_orientation,
+                run_probability: 0.5,
+            };
+            let removed_walls =
+                carve_sidewinder_into_grid_map(&grid_map, &mut Rng::with_seed(0), bias);
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+
+            let (_, run_direction) = run_orientation.get_directions();
+            let edge_cells: Vec<IVec2> = match run_orientation {
+                RunOrientation::Horizontal => (0..grid_map.get_columns() - 1)
+                    .map(|x| IVec2::new(x, grid_map.get_rows() - 1))
+                    .collect(),
+                RunOrientation::Vertical => (0..grid_map.get_rows() - 1)
+                    .map(|y| IVec2::new(grid_map.get_columns() - 1, y))
+                    .collect(),
+            };
+            assert!(edge_cells.into_iter().all(|cell_pos| is_wall_removed(
+                &grid_map,
+                &removed_walls,
+                cell_pos,
+                run_direction
+            )));
+        }
+    }
+
+    #[test]
+    fn run_probability_decides_how_long_the_runs_are() {
+        let grid_map = GridMap::new(10, 8);
+        for run_orientation in [RunOrientation::Horizontal, RunOrientation::Vertical] {
+            let (close_direction, run_direction) = run_orientation.get_directions();
+
+            // Always continuing the run carves every wall along the runs.
+            let bias = SidewinderBias {
+                run_orientation,
+                run_probability: 1.0,
+            };
+            let removed_walls =
+                carve_sidewinder_into_grid_map(&grid_map, &mut Rng::with_seed(0), bias);
+            for cell_pos in grid_map.iter_cells() {
+                let has_next = grid_map.inner_wall_from_cell_pos(cell_pos, run_direction);
+                assert_eq!(
+                    is_wall_removed(&grid_map, &removed_walls, cell_pos, run_direction),
+                    has_next.is_some()
+                );
+            }
+
+            // Never continuing closes every run straight away, apart from at the
+            // far edge.
+            let bias = SidewinderBias {
+                run_orientation,
+                run_probability: 0.0,
+            };
+            let removed_walls =
+                carve_sidewinder_into_grid_map(&grid_map, &mut Rng::with_seed(0), bias);
+            for cell_pos in grid_map.iter_cells() {
+                let can_close = grid_map.inner_wall_from_cell_pos(cell_pos, close_direction);
+                assert_eq!(
+                    is_wall_removed(&grid_map, &removed_walls, cell_pos, close_direction),
+                    can_close.is_some()
+                );
+            }
+        }
+    }
 }
@@ -1,99 +1,130 @@
+use super::{CellSet, MazeBuilder};
 use crate::components::Wall;
-use crate::resources::GridMap;
-use bevy::{prelude::*, utils::hashbrown::HashSet};
+use crate::resources::{Grid, GridMap, LayeredGrid, LayeredLink, LinkSet, WallSet};
+use bevy::{prelude::*, utils::HashSet};
 use fastrand::Rng;
 
-fn get_random_cell(grid_map: &GridMap, rng: &mut Rng) -> IVec2 {
-    let cell_count = grid_map.get_cell_count() as usize;
-    let random_start = rng.usize(0..cell_count);
-    return grid_map.index_to_cell_pos(random_start as i32).unwrap();
-}
-
-fn get_random_unvisited_cell(grid_map: &GridMap, visited: &HashSet<IVec2>, rng: &mut Rng) -> IVec2 {
-    let mut cell = get_random_cell(grid_map, rng);
+fn get_random_unvisited_cell<G: Grid + ?Sized>(
+    grid: &G,
+    visited: &CellSet,
+    rng: &mut Rng,
+) -> Option<G::Cell> {
+    let mut cell = grid.random_cell(rng)?;
 
-    while !visited.contains(&cell) {
-        cell = get_random_cell(grid_map, rng);
+    while visited.contains(grid, cell) {
+        cell = grid.random_cell(rng)?;
     }
 
-    return cell;
+    Some(cell)
+}
+
+#[deprecated(note = "use `carve_wilson_into_grid`, which carves any `Grid`")]
+pub fn carve_wilson_into_grid_map(grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+    carve_wilson_into_grid(grid_map, rng)
 }
 
-pub fn carve_wilson_into_grid_map(grid_map: &GridMap, rng_seed: u64) -> HashSet<Wall> {
-    let mut rng = Rng::with_seed(rng_seed);
-    let mut removed_walls = HashSet::new();
-    let mut visited = HashSet::new();
+pub fn carve_wilson_into_grid<G: Grid + ?Sized>(grid: &G, rng: &mut Rng) -> G::LinkSet {
+    let mut removed_walls = grid.new_link_set();
+    let mut visited = CellSet::new(grid);
     // The first cell is automatically marked as visited so that other 'paths'
     // the algorithm makes have somewhere to terminate.
-    visited.insert(IVec2 { x: 0, y: 0 });
+    let Some(first_cell) = grid.cells().next() else {
+        return removed_walls;
+    };
+    visited.insert(grid, first_cell);
 
-    let mut run: Vec<(IVec2, Wall)> = vec![];
-    let cell_count = grid_map.get_cell_count() as usize;
-    let mut current_pos = get_random_unvisited_cell(grid_map, &visited, &mut rng);
+    carve_loop_erased_walks(grid, rng, &mut visited, &mut removed_walls);
+
+    removed_walls
+}
+
+// Randomly walks from unvisited cells until the walk reaches a visited cell,
+// erasing any loops the walk makes along the way, and then carves the walk's
+// path. This repeats until every cell is visited.
+pub(super) fn carve_loop_erased_walks<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    visited: &mut CellSet,
+    removed_walls: &mut G::LinkSet,
+) {
+    let cell_count = grid.cell_count();
+    if visited.is_empty() {
+        // Something went wrong, there's nowhere for the walks to terminate.
+        return;
+    }
+
+    // The neighbour each cell was last left through, and the wall between them.
+    // Leaving a cell again replaces its exit, which erases the loop the walk made
+    // since it last left that cell, so each step of a walk takes the same time.
+    let mut exits: Vec<Option<(G::Cell, G::Link)>> = vec![None; grid.cell_index_count()];
 
     while visited.len() < cell_count {
-        let neighbours = vec![
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::NORTH),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::NORTH),
-            ),
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::EAST),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::EAST),
-            ),
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::SOUTH),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::SOUTH),
-            ),
-            (
-                grid_map.neighbour_from_cell_pos(current_pos, Dir2::WEST),
-                grid_map.inner_wall_from_cell_pos(current_pos, Dir2::WEST),
-            ),
-        ];
-
-        let possible_neighbours: Vec<(Option<IVec2>, Option<Wall>)> = neighbours
-            .into_iter()
-            .filter(|(neighbour, wall)| neighbour.is_some() && wall.is_some())
-            .collect();
-
-        let num_possible_neighbours = possible_neighbours.len();
-        let random_neighbour_index = rng.usize(0..(num_possible_neighbours));
-        let neighbour = possible_neighbours.into_iter().nth(random_neighbour_index);
-
-        if let Some((Some(neighbour_cell), Some(neighbour_wall))) = neighbour {
-            // Check for possible loops in the current run, where we've returned to a
-            // cell that was already part of the run.
-            let loop_result = run
-                .iter()
-                .enumerate()
-                .find(|(_, (run_cell, _))| *run_cell == neighbour_cell);
-
-            // If a loop is found undo it, return to where the loop started, resetting the run
-            // to that point.
-            if let Some((loop_start_index, (loop_start_cell, _))) = loop_result {
-                current_pos = *loop_start_cell;
-                run.truncate(loop_start_index + 1);
-                continue;
-            }
+        let Some(start_pos) = get_random_unvisited_cell(grid, visited, rng) else {
+            return;
+        };
+        let mut current_pos = start_pos;
+
+        while !visited.contains(grid, current_pos) {
+            let possible_neighbours = grid.neighbour_walls(current_pos);
 
-            // Complete this run and carve walls.
-            if visited.contains(&neighbour_cell) {
-                run.iter().for_each(|(cell, wall)| {
-                    visited.insert(*cell);
-                    removed_walls.insert(*wall);
-                });
-                run.clear();
-                current_pos = get_random_unvisited_cell(grid_map, &visited, &mut rng);
-                continue;
+            if possible_neighbours.is_empty() {
+                // Something went wrong, a neighbour couldn't be found.
+                return;
             }
 
-            // If those other things didn't happen, continue the run.
-            run.push((neighbour_cell, neighbour_wall));
-        } else {
-            // Something went wrong, a neighbour couldn't be found.
-            break;
+            let random_neighbour_index = rng.usize(0..possible_neighbours.len());
+            let exit = possible_neighbours[random_neighbour_index];
+            let Some(current_index) = grid.cell_index(current_pos) else {
+                return;
+            };
+            exits[current_index] = Some(exit);
+            current_pos = exit.0;
+        }
+
+        // The walk has joined the maze, so follow the exits from the start of the
+        // walk to carve its path without the loops.
+        let mut current_pos = start_pos;
+        while !visited.contains(grid, current_pos) {
+            let Some((next_pos, wall)) =
+                grid.cell_index(current_pos).and_then(|index| exits[index])
+            else {
+                return;
+            };
+            visited.insert(grid, current_pos);
+            removed_walls.insert(wall);
+            current_pos = next_pos;
         }
     }
+}
 
-    removed_walls
+pub struct Wilson;
+
+impl MazeBuilder for Wilson {
+    fn name(&self) -> &str {
+        "Wilson"
+    }
+
+    fn description(&self) -> &str {
+        "Loop-erased random walks from unvisited cells until they reach the maze. Produces an unbiased maze, but starts slowly."
+    }
+
+    fn carve(&self, grid_map: &GridMap, rng: &mut Rng) -> WallSet {
+        carve_wilson_into_grid(grid_map, rng)
+    }
+
+    fn carve_grid(
+        &self,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        rng: &mut Rng,
+    ) -> Option<WallSet> {
+        Some(carve_wilson_into_grid(grid, rng))
+    }
+
+    fn carve_layered_grid(
+        &self,
+        grid: &LayeredGrid,
+        rng: &mut Rng,
+    ) -> Option<HashSet<LayeredLink>> {
+        Some(carve_wilson_into_grid(grid, rng))
+    }
 }
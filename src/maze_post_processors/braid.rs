@@ -0,0 +1,207 @@
+use crate::resources::{Grid, LinkSet};
+use bevy::prelude::*;
+use fastrand::Rng;
+
+#[derive(Resource, Copy, Clone, PartialEq)]
+pub struct Braid {
+    // The chance, between 0.0 and 1.0, that each dead end is removed.
+    pub dead_end_probability: f32,
+}
+
+impl Default for Braid {
+    fn default() -> Self {
+        Braid {
+            dead_end_probability: 0.0,
+        }
+    }
+}
+
+// Returns the walls around a cell that lead to another cell, with whether
+// they've been removed.
+fn get_inner_walls<G: Grid + ?Sized>(
+    grid: &G,
+    removed_walls: &G::LinkSet,
+    cell_pos: G::Cell,
+) -> Vec<(G::Cell, G::Link, bool)> {
+    grid.neighbour_walls(cell_pos)
+        .into_iter()
+        .map(|(neighbour, wall)| (neighbour, wall, removed_walls.contains(&wall)))
+        .collect()
+}
+
+pub fn is_dead_end<G: Grid + ?Sized>(
+    grid: &G,
+    removed_walls: &G::LinkSet,
+    cell_pos: G::Cell,
+) -> bool {
+    get_inner_walls(grid, removed_walls, cell_pos)
+        .into_iter()
+        .filter(|(_, _, is_removed)| *is_removed)
+        .count()
+        == 1
+}
+
+pub fn get_dead_ends<G: Grid + ?Sized>(grid: &G, removed_walls: &G::LinkSet) -> Vec<G::Cell> {
+    grid.cells()
+        .filter(|cell_pos| is_dead_end(grid, removed_walls, *cell_pos))
+        .collect()
+}
+
+// Adds loops to a maze by knocking an extra wall out of some of its dead ends.
+// Joining two dead ends together is preferred, as that removes both at once,
+// otherwise the wall to any other neighbour is removed.
+pub fn braid_removed_walls<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    removed_walls: &mut G::LinkSet,
+    braid: Braid,
+) {
+    let mut dead_ends = get_dead_ends(grid, removed_walls);
+    rng.shuffle(&mut dead_ends);
+
+    for cell_pos in dead_ends {
+        // An earlier dead end may have been joined to this one already.
+        if !is_dead_end(grid, removed_walls, cell_pos) || rng.f32() >= braid.dead_end_probability {
+            continue;
+        }
+
+        let candidates: Vec<(G::Cell, G::Link)> = get_inner_walls(grid, removed_walls, cell_pos)
+            .into_iter()
+            .filter(|(_, _, is_removed)| !is_removed)
+            .map(|(neighbour, wall, _)| (neighbour, wall))
+            .collect();
+        let dead_end_candidates: Vec<(G::Cell, G::Link)> = candidates
+            .iter()
+            .copied()
+            .filter(|(neighbour, _)| is_dead_end(grid, removed_walls, *neighbour))
+            .collect();
+
+        let candidates = if dead_end_candidates.is_empty() {
+            candidates
+        } else {
+            dead_end_candidates
+        };
+        if let Some((_, wall)) = rng.choice(candidates) {
+            removed_walls.insert(wall);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_builders::carve_wilson_into_grid;
+    use crate::resources::GridMap;
+    use crate::utils::{dijkstra, get_path};
+
+    #[test]
+    fn braiding_every_dead_end_leaves_none() {
+        let grid_map = GridMap::new(12, 10);
+        for seed in 0..5 {
+            let mut rng = Rng::with_seed(seed);
+            let mut removed_walls = carve_wilson_into_grid(&grid_map, &mut rng);
+            assert!(!get_dead_ends(&grid_map, &removed_walls).is_empty());
+
+            braid_removed_walls(
+                &grid_map,
+                &mut rng,
+                &mut removed_walls,
+                Braid {
+                    dead_end_probability: 1.0,
+                },
+            );
+            assert!(get_dead_ends(&grid_map, &removed_walls).is_empty());
+        }
+    }
+
+    #[test]
+    fn braiding_no_dead_ends_changes_nothing() {
+        let grid_map = GridMap::new(12, 10);
+        let mut rng = Rng::with_seed(0);
+        let carved_walls = carve_wilson_into_grid(&grid_map, &mut rng);
+        let mut removed_walls = carved_walls.clone();
+
+        braid_removed_walls(&grid_map, &mut rng, &mut removed_walls, Braid::default());
+        assert!(removed_walls == carved_walls);
+    }
+
+    #[test]
+    fn braiding_only_knocks_out_walls_next_to_dead_ends() {
+        let grid_map = GridMap::new(12, 10);
+        for seed in 0..5 {
+            let mut rng = Rng::with_seed(seed);
+            let carved_walls = carve_wilson_into_grid(&grid_map, &mut rng);
+            let dead_ends = get_dead_ends(&grid_map, &carved_walls);
+            let mut removed_walls = carved_walls.clone();
+
+            braid_removed_walls(
+                &grid_map,
+                &mut rng,
+                &mut removed_walls,
+                Braid {
+                    dead_end_probability: 0.5,
+                },
+            );
+
+            let added_walls: Vec<_> = removed_walls
+                .iter()
+                .filter(|wall| !carved_walls.contains(wall))
+                .collect();
+            assert!(carved_walls
+                .iter()
+                .all(|wall| removed_walls.contains(&wall)));
+            assert!(!added_walls.is_empty());
+            assert!(added_walls.len() < dead_ends.len());
+            for wall in added_walls {
+                let (cell, neighbour) = grid_map.cells_from_inner_wall(wall).unwrap();
+                assert!(dead_ends.contains(&cell) || dead_ends.contains(&neighbour));
+            }
+        }
+    }
+
+    // A maze that winds around a 3x2 grid, from the bottom left up, across and
+    // back down to the cell next to where it started.
+    #[test]
+    fn joining_two_dead_ends_gives_the_shortest_path_through_the_loop() {
+        let grid_map = GridMap::new(3, 2);
+        let route = [
+            IVec2::new(0, 0),
+            IVec2::new(0, 1),
+            IVec2::new(1, 1),
+            IVec2::new(2, 1),
+            IVec2::new(2, 0),
+            IVec2::new(1, 0),
+        ];
+        let mut carved_walls = grid_map.new_link_set();
+        carved_walls.extend(
+            route
+                .windows(2)
+                .map(|cells| grid_map.wall_between(cells[0], cells[1]).unwrap()),
+        );
+        let (start, end) = (route[0], route[5]);
+        let distances = dijkstra(start, &grid_map, &carved_walls);
+        assert_eq!(distances.get(&grid_map, end), Some(5));
+
+        // The two dead ends are next to each other, so they're joined, even though
+        // the bottom middle could also have been joined to the cell above it. The
+        // seeds cover both dead ends being braided first.
+        for seed in 0..10 {
+            let mut removed_walls = carved_walls.clone();
+            braid_removed_walls(
+                &grid_map,
+                &mut Rng::with_seed(seed),
+                &mut removed_walls,
+                Braid {
+                    dead_end_probability: 1.0,
+                },
+            );
+            assert_eq!(removed_walls.len(), 6);
+            assert!(removed_walls.contains(&grid_map.wall_between(start, end).unwrap()));
+
+            let distances = dijkstra(start, &grid_map, &removed_walls);
+            assert_eq!(distances.get(&grid_map, end), Some(1));
+            let path = get_path(start, end, &distances, &grid_map, &removed_walls);
+            assert_eq!(path.len(), 2);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod braid;
+mod sparse;
+
+pub use braid::*;
+pub use sparse::*;
@@ -0,0 +1,151 @@
+use super::get_dead_ends;
+use crate::resources::{Grid, LinkSet};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+
+#[derive(Resource, Copy, Clone, PartialEq)]
+pub enum Sparseness {
+    // Fills in every dead end this many times. Each pass shortens every dead
+    // end passage by one cell.
+    Passes(u32),
+    // Keeps filling in dead ends until this fraction of the cells, between 0.0
+    // and 1.0, have been filled in.
+    CulledFraction(f32),
+}
+
+impl Default for Sparseness {
+    fn default() -> Self {
+        Sparseness::Passes(0)
+    }
+}
+
+// Fills in dead ends by putting back the wall that leads into them, which turns
+// them into solid rock. Returns the cells that were filled in, so they can be
+// marked as inaccessible. Filling in a dead end always leaves its neighbour,
+// so at least one cell is left.
+pub fn cull_dead_ends<G: Grid + ?Sized>(
+    grid: &G,
+    rng: &mut Rng,
+    removed_walls: &mut G::LinkSet,
+    sparseness: Sparseness,
+) -> HashSet<G::Cell> {
+    let (max_passes, target_culled_count) = match sparseness {
+        Sparseness::Passes(passes) => (passes, usize::MAX),
+        Sparseness::CulledFraction(fraction) => (
+            u32::MAX,
+            (grid.cell_count() as f32 * fraction.clamp(0.0, 1.0)) as usize,
+        ),
+    };
+    let mut culled_cells = HashSet::new();
+
+    for _ in 0..max_passes {
+        let mut dead_ends = get_dead_ends(grid, removed_walls);
+        if dead_ends.is_empty() {
+            break;
+        }
+        // When only part of a pass is needed, this stops one side of the grid
+        // being filled in before the other.
+        rng.shuffle(&mut dead_ends);
+
+        for cell_pos in dead_ends {
+            if culled_cells.len() >= target_culled_count {
+                return culled_cells;
+            }
+
+            // Two dead ends joined together are both found in the same pass,
+            // and only one of them should be filled in.
+            let removed_wall = grid
+                .neighbour_walls(cell_pos)
+                .into_iter()
+                .map(|(_, wall)| wall)
+                .find(|wall| removed_walls.contains(wall));
+            if let Some(removed_wall) = removed_wall {
+                removed_walls.remove(&removed_wall);
+                culled_cells.insert(cell_pos);
+            }
+        }
+    }
+
+    culled_cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_builders::carve_wilson_into_grid;
+    use crate::resources::{GridMap, WallSet};
+    use crate::utils::dijkstra;
+
+    // The cells that weren't culled should all still be reachable from each other,
+    // and the culled ones shouldn't have any passages left.
+    fn assert_culled(grid_map: &GridMap, removed_walls: &WallSet, culled_cells: &HashSet<IVec2>) {
+        let start = grid_map
+            .cells()
+            .find(|cell_pos| !culled_cells.contains(cell_pos))
+            .unwrap();
+        let distances = dijkstra(start, grid_map, removed_walls);
+        assert_eq!(distances.len(), grid_map.cell_count() - culled_cells.len());
+        assert!(distances
+            .iter()
+            .all(|(cell_pos, _)| !culled_cells.contains(&cell_pos)));
+
+        culled_cells.iter().for_each(|cell_pos| {
+            assert!(grid_map
+                .neighbour_walls(*cell_pos)
+                .iter()
+                .all(|(_, wall)| !removed_walls.contains(wall)));
+        });
+    }
+
+    #[test]
+    fn passes_fill_in_dead_ends_and_keep_the_rest_connected() {
+        let grid_map = GridMap::new(12, 10);
+        for passes in [1, 3] {
+            let mut rng = Rng::with_seed(0);
+            let mut removed_walls = carve_wilson_into_grid(&grid_map, &mut rng);
+            let dead_end_count = get_dead_ends(&grid_map, &removed_walls).len();
+
+            let culled_cells = cull_dead_ends(
+                &grid_map,
+                &mut rng,
+                &mut removed_walls,
+                Sparseness::Passes(passes),
+            );
+            assert!(culled_cells.len() >= dead_end_count / 2);
+            assert_culled(&grid_map, &removed_walls, &culled_cells);
+        }
+    }
+
+    #[test]
+    fn culled_fraction_fills_in_that_many_cells() {
+        let grid_map = GridMap::new(12, 10);
+        let mut rng = Rng::with_seed(0);
+        let mut removed_walls = carve_wilson_into_grid(&grid_map, &mut rng);
+
+        let culled_cells = cull_dead_ends(
+            &grid_map,
+            &mut rng,
+            &mut removed_walls,
+            Sparseness::CulledFraction(0.5),
+        );
+        assert_eq!(culled_cells.len(), 60);
+        assert_culled(&grid_map, &removed_walls, &culled_cells);
+    }
+
+    #[test]
+    fn no_passes_changes_nothing() {
+        let grid_map = GridMap::new(12, 10);
+        let mut rng = Rng::with_seed(0);
+        let carved_walls = carve_wilson_into_grid(&grid_map, &mut rng);
+        let mut removed_walls = carved_walls.clone();
+
+        let culled_cells = cull_dead_ends(
+            &grid_map,
+            &mut rng,
+            &mut removed_walls,
+            Sparseness::default(),
+        );
+        assert!(culled_cells.is_empty());
+        assert!(removed_walls == carved_walls);
+    }
+}
@@ -0,0 +1,151 @@
+use crate::utils::DisjointSet;
+use bevy::prelude::*;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CellMaskError {
+    NoEnabledCells,
+    // Builders can't carve between regions of cells that don't touch.
+    DisconnectedCells { region_count: usize },
+}
+
+impl fmt::Display for CellMaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CellMaskError::NoEnabledCells => write!(f, "the mask doesn't have any cells turned on"),
+            CellMaskError::DisconnectedCells { region_count } => write!(
+                f,
+                "the cells that are turned on form {region_count} separate regions, they must all be connected"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CellMaskError {}
+
+// Turns individual cells of a grid on or off, so that mazes can be any shape
+// rather than always being a full rectangle.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CellMask {
+    columns: i32,
+    rows: i32,
+    // Whether each cell is on, by cell index.
+    cells: Vec<bool>,
+    enabled_count: usize,
+}
+
+impl CellMask {
+    // Creates a mask with every cell turned on.
+    pub fn new(columns: i32, rows: i32) -> Self {
+        CellMask {
+            columns,
+            rows,
+            cells: vec![true; (columns * rows).max(0) as usize],
+            enabled_count: (columns * rows).max(0) as usize,
+        }
+    }
+
+    pub fn get_columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> i32 {
+        self.rows
+    }
+
+    fn cell_pos_to_index(&self, cell_pos: IVec2) -> Option<usize> {
+        if cell_pos.x < 0 || cell_pos.x >= self.columns || cell_pos.y < 0 || cell_pos.y >= self.rows
+        {
+            return None;
+        }
+
+        Some((cell_pos.y * self.columns + cell_pos.x) as usize)
+    }
+
+    // Cells outside of the mask are always off.
+    pub fn is_enabled(&self, cell_pos: IVec2) -> bool {
+        self.cell_pos_to_index(cell_pos)
+            .is_some_and(|index| self.cells[index])
+    }
+
+    pub fn set_enabled(&mut self, cell_pos: IVec2, is_enabled: bool) {
+        if let Some(index) = self.cell_pos_to_index(cell_pos) {
+            if self.cells[index] != is_enabled {
+                self.cells[index] = is_enabled;
+                if is_enabled {
+                    self.enabled_count += 1;
+                } else {
+                    self.enabled_count -= 1;
+                }
+            }
+        }
+    }
+
+    pub fn get_enabled_count(&self) -> usize {
+        self.enabled_count
+    }
+
+    // Checks the mask can be carved into a maze, which needs at least one cell
+    // that's on, and every cell that's on to be connected to the others.
+    pub fn validate(&self) -> Result<(), CellMaskError> {
+        if self.enabled_count == 0 {
+            return Err(CellMaskError::NoEnabledCells);
+        }
+
+        // Join every pair of neighbouring cells that are on, then count the regions.
+        let mut sets = DisjointSet::new(self.cells.len());
+        let enabled_cells: Vec<IVec2> = (0..self.rows)
+            .flat_map(|y| (0..self.columns).map(move |x| IVec2 { x, y }))
+            .filter(|cell_pos| self.is_enabled(*cell_pos))
+            .collect();
+        let to_index = |cell_pos: IVec2| (cell_pos.y * self.columns + cell_pos.x) as usize;
+        enabled_cells.iter().for_each(|cell_pos| {
+            [IVec2::X, IVec2::Y].into_iter().for_each(|offset| {
+                if self.is_enabled(*cell_pos + offset) {
+                    sets.union(to_index(*cell_pos), to_index(*cell_pos + offset));
+                }
+            });
+        });
+
+        // Each cell that's off is still a set of its own.
+        let region_count = sets.set_count() - (sets.len() - enabled_cells.len());
+        if region_count > 1 {
+            return Err(CellMaskError::DisconnectedCells { region_count });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::GridMap;
+
+    #[test]
+    fn connected_mask_is_valid() {
+        let mut mask = CellMask::new(3, 3);
+        mask.set_enabled(IVec2::new(1, 1), false);
+        assert_eq!(mask.validate(), Ok(()));
+        assert!(GridMap::with_mask(mask).is_ok());
+    }
+
+    #[test]
+    fn mask_with_separate_regions_is_rejected() {
+        let mut mask = CellMask::new(3, 2);
+        mask.set_enabled(IVec2::new(1, 0), false);
+        mask.set_enabled(IVec2::new(1, 1), false);
+        assert_eq!(
+            mask.validate(),
+            Err(CellMaskError::DisconnectedCells { region_count: 2 })
+        );
+        assert!(GridMap::with_mask(mask).is_err());
+    }
+
+    #[test]
+    fn mask_without_cells_is_rejected() {
+        let mut mask = CellMask::new(1, 1);
+        mask.set_enabled(IVec2::ZERO, false);
+        assert_eq!(mask.validate(), Err(CellMaskError::NoEnabledCells));
+    }
+}
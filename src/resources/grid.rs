@@ -0,0 +1,320 @@
+use super::{GridMap, WallSet};
+use crate::components::{Wall, WallOrientation};
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+use std::hash::Hash;
+
+// The parts of a grid that builders and solvers need, so that the ones that only
+// walk from cell to cell work on any shape of grid, not only square grid maps.
+//
+// A grid decides how its cells are identified, and how the link between two
+// neighbouring cells is identified. A maze is the set of links whose walls have
+// been removed. All of this crate's grids apart from the `LayeredGrid` and
+// `WeaveGrid` use an `IVec2` for cells and a `Wall` for links, what the numbers
+// mean is up to the grid, and keep their removed walls in a `WallSet`.
+pub trait Grid: Send + Sync {
+    type Cell: Copy + Eq + Hash + Send + Sync;
+    type Link: Copy + Eq + Hash + Send + Sync;
+    type LinkSet: LinkSet<Self::Link>;
+
+    // The number of cells that are part of the maze.
+    fn cell_count(&self) -> usize;
+
+    // Every cell that's part of the maze, always in the same order.
+    fn cells(&self) -> Box<dyn Iterator<Item = Self::Cell> + '_>;
+
+    // A number for each cell, from 0 up to `cell_index_count`, so that things
+    // about each cell can be kept in a `Vec`. Cells that aren't part of the maze,
+    // like a grid map's cells that are turned off, can still have an index.
+    fn cell_index(&self, cell: Self::Cell) -> Option<usize>;
+
+    fn cell_index_count(&self) -> usize;
+
+    // Returns `None` if there are no cells.
+    fn random_cell(&self, rng: &mut Rng) -> Option<Self::Cell>;
+
+    fn neighbours(&self, cell: Self::Cell) -> Vec<Self::Cell>;
+
+    // Returns `None` if the cells aren't neighbours.
+    fn wall_between(&self, from: Self::Cell, to: Self::Cell) -> Option<Self::Link>;
+
+    // An empty set that can hold any of the grid's links.
+    fn new_link_set(&self) -> Self::LinkSet;
+
+    // Each neighbour with the link between it and `cell`.
+    fn neighbour_walls(&self, cell: Self::Cell) -> Vec<(Self::Cell, Self::Link)> {
+        self.neighbours(cell)
+            .into_iter()
+            .filter_map(|neighbour| Some((neighbour, self.wall_between(cell, neighbour)?)))
+            .collect()
+    }
+
+    // Calls `visit` with each neighbour and the link between it and `cell`, like
+    // `neighbour_walls` but without collecting them. Searches through big mazes
+    // use this, so grids can override it to avoid allocating for every cell.
+    fn for_each_neighbour_wall(
+        &self,
+        cell: Self::Cell,
+        visit: &mut dyn FnMut(Self::Cell, Self::Link),
+    ) {
+        self.neighbour_walls(cell)
+            .into_iter()
+            .for_each(|(neighbour, wall)| visit(neighbour, wall));
+    }
+
+    // Calls `visit` with each neighbour that's linked to `cell` in `links`, along
+    // with the neighbour's `cell_index`. Solving a maze does little else, so grids
+    // can override this to skip making each link just to look it up.
+    fn for_each_linked_neighbour(
+        &self,
+        cell: Self::Cell,
+        links: &Self::LinkSet,
+        visit: &mut dyn FnMut(Self::Cell, usize),
+    ) {
+        visit_linked_neighbours(self, cell, links, visit);
+    }
+}
+
+fn visit_linked_neighbours<G: Grid + ?Sized>(
+    grid: &G,
+    cell: G::Cell,
+    links: &G::LinkSet,
+    visit: &mut dyn FnMut(G::Cell, usize),
+) {
+    grid.for_each_neighbour_wall(cell, &mut |neighbour, link| {
+        if !links.contains(&link) {
+            return;
+        }
+        if let Some(neighbour_index) = grid.cell_index(neighbour) {
+            visit(neighbour, neighbour_index);
+        }
+    });
+}
+
+// A set of a grid's links, which is how a maze is stored.
+pub trait LinkSet<L>: Clone + PartialEq + Extend<L> + Send + Sync {
+    // Returns whether the link wasn't already in the set.
+    fn insert(&mut self, link: L) -> bool;
+
+    // Returns whether the link was in the set.
+    fn remove(&mut self, link: &L) -> bool;
+
+    fn contains(&self, link: &L) -> bool;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = L> + '_>;
+}
+
+impl LinkSet<Wall> for WallSet {
+    fn insert(&mut self, link: Wall) -> bool {
+        WallSet::insert(self, link)
+    }
+
+    fn remove(&mut self, link: &Wall) -> bool {
+        WallSet::remove(self, link)
+    }
+
+    fn contains(&self, link: &Wall) -> bool {
+        WallSet::contains(self, link)
+    }
+
+    fn len(&self) -> usize {
+        WallSet::len(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = Wall> + '_> {
+        Box::new(WallSet::iter(self))
+    }
+}
+
+// For links that don't have a more compact set. Unlike a `WallSet`, the order it
+// iterates in depends on the order the links were added in.
+impl<L: Copy + Eq + Hash + Send + Sync> LinkSet<L> for HashSet<L> {
+    fn insert(&mut self, link: L) -> bool {
+        HashSet::insert(self, link)
+    }
+
+    fn remove(&mut self, link: &L) -> bool {
+        HashSet::remove(self, link)
+    }
+
+    fn contains(&self, link: &L) -> bool {
+        HashSet::contains(self, link)
+    }
+
+    fn len(&self) -> usize {
+        HashSet::len(self)
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = L> + '_> {
+        Box::new(HashSet::iter(self).copied())
+    }
+}
+
+// The shapes needed to draw a grid that isn't a square grid map, in grid units.
+pub trait DrawableGrid: Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet> {
+    fn get_cell_center(&self, cell: IVec2) -> Vec2;
+
+    // The corners of the cell going counter-clockwise. Cells are drawn as a fan of
+    // triangles from their center, so it has to be able to see every corner.
+    fn get_cell_outline(&self, cell: IVec2) -> Vec<Vec2>;
+
+    // The points along the wall between two cells.
+    fn get_wall_outline(&self, wall: Wall) -> Vec<Vec2>;
+
+    // The edges around the outside of the grid, which are never removed.
+    fn get_boundary_outlines(&self) -> Vec<Vec<Vec2>>;
+
+    fn get_scale_from_available_space(&self, available_space: Vec2) -> f32;
+
+    // The position to move the grid to, once scaled, so it's in the middle of the screen.
+    fn get_centered_grid_pos(&self, scale: f32) -> Vec2;
+
+    // Every wall between two cells, each one only once.
+    fn get_walls(&self) -> Vec<Wall> {
+        let mut seen_walls = HashSet::new();
+        self.cells()
+            .flat_map(|cell| self.neighbour_walls(cell))
+            .map(|(_, wall)| wall)
+            .filter(|wall| seen_walls.insert(*wall))
+            .collect()
+    }
+}
+
+const DIRECTIONS: [Dir2; 4] = [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST];
+
+impl Grid for GridMap {
+    type Cell = IVec2;
+    type Link = Wall;
+    type LinkSet = WallSet;
+
+    fn cell_count(&self) -> usize {
+        self.get_enabled_cell_count() as usize
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec2> + '_> {
+        Box::new(self.iter_cells())
+    }
+
+    fn cell_index(&self, cell: IVec2) -> Option<usize> {
+        Some(self.cell_pos_to_index(cell)? as usize)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec2> {
+        self.random_cell_pos(rng)
+    }
+
+    fn neighbours(&self, cell: IVec2) -> Vec<IVec2> {
+        self.neighbour_walls(cell)
+            .into_iter()
+            .map(|(neighbour, _)| neighbour)
+            .collect()
+    }
+
+    fn wall_between(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+        self.inner_wall_between_cell_pos(from, to)
+    }
+
+    fn new_link_set(&self) -> WallSet {
+        WallSet::new(self.get_columns(), self.get_rows())
+    }
+
+    fn neighbour_walls(&self, cell: IVec2) -> Vec<(IVec2, Wall)> {
+        let mut neighbour_walls = Vec::with_capacity(DIRECTIONS.len());
+        self.for_each_neighbour_wall(cell, &mut |neighbour, wall| {
+            neighbour_walls.push((neighbour, wall));
+        });
+        neighbour_walls
+    }
+
+    fn for_each_neighbour_wall(&self, cell: IVec2, visit: &mut dyn FnMut(IVec2, Wall)) {
+        // The same as `inner_wall_from_cell_pos` for each direction, but the cell
+        // is only checked once rather than for every direction.
+        if !self.is_cell_enabled(cell) {
+            return;
+        }
+        DIRECTIONS.into_iter().for_each(|direction| {
+            let Some(neighbour) = self.neighbour_from_cell_pos(cell, direction) else {
+                return;
+            };
+            if let Some(wall) = self.wall_from_cell_pos(cell, direction) {
+                visit(neighbour, self.wrap_wall(wall));
+            }
+        });
+    }
+
+    fn for_each_linked_neighbour(
+        &self,
+        cell: IVec2,
+        removed_walls: &WallSet,
+        visit: &mut dyn FnMut(IVec2, usize),
+    ) {
+        // Without a mask or wrapping, and with walls for a grid map of the same
+        // size, the neighbours and the walls between them are found from the
+        // cell's position alone.
+        let is_plain = self.get_mask().is_none()
+            && !self.is_wrapping_horizontally()
+            && !self.is_wrapping_vertically()
+            && removed_walls.get_columns() == self.get_columns()
+            && removed_walls.get_rows() == self.get_rows();
+        if !is_plain || !self.is_cell_pos_in_bounds(cell) {
+            visit_linked_neighbours(self, cell, removed_walls, visit);
+            return;
+        }
+
+        // See `wall_pos_to_index` for how the walls are indexed.
+        let columns = self.get_columns() as usize;
+        let (x, y) = (cell.x as usize, cell.y as usize);
+        let index = y * columns + x;
+        let mut visit_if_removed = |orientation, wall_index, neighbour, neighbour_index| {
+            if removed_walls.contains_index(orientation, wall_index) {
+                visit(neighbour, neighbour_index);
+            }
+        };
+
+        if cell.y + 1 < self.get_rows() {
+            let wall_index = index + columns;
+            visit_if_removed(
+                WallOrientation::Horizontal,
+                wall_index,
+                cell + IVec2::Y,
+                index + columns,
+            );
+        }
+        if cell.x + 1 < self.get_columns() {
+            let wall_index = y * (columns + 1) + x + 1;
+            visit_if_removed(
+                WallOrientation::Vertical,
+                wall_index,
+                cell + IVec2::X,
+                index + 1,
+            );
+        }
+        if cell.y > 0 {
+            visit_if_removed(
+                WallOrientation::Horizontal,
+                index,
+                cell - IVec2::Y,
+                index - columns,
+            );
+        }
+        if cell.x > 0 {
+            let wall_index = y * (columns + 1) + x;
+            visit_if_removed(
+                WallOrientation::Vertical,
+                wall_index,
+                cell - IVec2::X,
+                index - 1,
+            );
+        }
+    }
+}
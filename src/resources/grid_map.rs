@@ -1,5 +1,7 @@
+use super::{CellMask, CellMaskError};
 use crate::components::*;
 use bevy::prelude::*;
+use fastrand::Rng;
 
 pub enum Direction {
     Up,
@@ -8,15 +10,136 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Resource)]
+// Whether cells on opposite edges of a grid map are neighbours. The wall between
+// them, the seam, is the wall along the west edge for `Horizontal` wrapping and
+// the wall along the south edge for vertical wrapping. The walls along the east
+// and north edges are the same walls seen from the other side.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum WrapMode {
+    #[default]
+    None,
+    // The west and east edges join up, like a cylinder.
+    Horizontal,
+    // Both pairs of edges join up, like a torus.
+    Both,
+}
+
+#[derive(Resource, Clone)]
 pub struct GridMap {
     columns: i32,
     rows: i32,
+    // Cells that are turned off aren't part of the maze. They're skipped when
+    // iterating cells and are never returned as neighbours.
+    mask: Option<CellMask>,
+    wrap_mode: WrapMode,
 }
 
 impl GridMap {
     pub fn new(columns: i32, rows: i32) -> Self {
-        GridMap { columns, rows }
+        GridMap {
+            columns,
+            rows,
+            mask: None,
+            wrap_mode: WrapMode::None,
+        }
+    }
+
+    // Creates a grid map the size of the mask. The cells that are on must all be
+    // connected to each other, otherwise builders couldn't reach every cell.
+    pub fn with_mask(mask: CellMask) -> Result<Self, CellMaskError> {
+        mask.validate()?;
+        Ok(GridMap {
+            columns: mask.get_columns(),
+            rows: mask.get_rows(),
+            mask: Some(mask),
+            wrap_mode: WrapMode::None,
+        })
+    }
+
+    pub fn get_wrap_mode(&self) -> WrapMode {
+        self.wrap_mode
+    }
+
+    pub fn set_wrap_mode(&mut self, wrap_mode: WrapMode) {
+        self.wrap_mode = wrap_mode;
+    }
+
+    // A copy of the grid map that doesn't wrap, for builders that work along rows
+    // and columns and need to stop at the edges.
+    pub fn without_wrapping(&self) -> GridMap {
+        GridMap {
+            wrap_mode: WrapMode::None,
+            ..self.clone()
+        }
+    }
+
+    // A row or column needs at least three cells to wrap, otherwise a cell would
+    // be its own neighbour, or have the same neighbour on both sides.
+    pub fn is_wrapping_horizontally(&self) -> bool {
+        self.wrap_mode != WrapMode::None && self.columns > 2
+    }
+
+    pub fn is_wrapping_vertically(&self) -> bool {
+        self.wrap_mode == WrapMode::Both && self.rows > 2
+    }
+
+    // Moves a position that's gone past a wrapping edge around to the other side.
+    fn wrap_cell_pos(&self, cell_pos: IVec2) -> IVec2 {
+        IVec2 {
+            x: match self.is_wrapping_horizontally() {
+                true => cell_pos.x.rem_euclid(self.columns),
+                false => cell_pos.x,
+            },
+            y: match self.is_wrapping_vertically() {
+                true => cell_pos.y.rem_euclid(self.rows),
+                false => cell_pos.y,
+            },
+        }
+    }
+
+    // Returns the seam wall on the west or south edge for a wall on the east or
+    // north edge that wraps around, otherwise returns the wall unchanged.
+    pub fn wrap_wall(&self, wall: Wall) -> Wall {
+        let is_horizontal = wall.from.y == wall.to.y;
+        if !is_horizontal && wall.from.x == self.columns && self.is_wrapping_horizontally() {
+            let offset = IVec2 {
+                x: self.columns,
+                y: 0,
+            };
+            return Wall {
+                from: wall.from - offset,
+                to: wall.to - offset,
+            };
+        }
+        if is_horizontal && wall.from.y == self.rows && self.is_wrapping_vertically() {
+            let offset = IVec2 { x: 0, y: self.rows };
+            return Wall {
+                from: wall.from - offset,
+                to: wall.to - offset,
+            };
+        }
+
+        wall
+    }
+
+    // Returns whether a wall is a seam, which joins cells on opposite edges.
+    pub fn is_seam_wall(&self, wall: Wall) -> bool {
+        let is_horizontal = wall.from.y == wall.to.y;
+        match is_horizontal {
+            true => wall.from.y == 0 && self.is_wrapping_vertically(),
+            false => wall.from.x == 0 && self.is_wrapping_horizontally(),
+        }
+    }
+
+    pub fn get_mask(&self) -> Option<&CellMask> {
+        self.mask.as_ref()
+    }
+
+    pub fn is_cell_enabled(&self, cell_pos: IVec2) -> bool {
+        match &self.mask {
+            Some(mask) => mask.is_enabled(cell_pos),
+            None => self.is_cell_pos_in_bounds(cell_pos),
+        }
     }
 
     pub fn wall_from_cell_pos(&self, cell_pos: IVec2, direction: Dir2) -> Option<Wall> {
@@ -57,32 +180,109 @@ impl GridMap {
         }
     }
 
+    // Returns the seam wall when the neighbour is on the opposite edge.
     pub fn inner_wall_from_cell_pos(&self, cell_pos: IVec2, direction: Dir2) -> Option<Wall> {
+        let is_wrapping_horizontally = self.is_wrapping_horizontally();
+        let is_wrapping_vertically = self.is_wrapping_vertically();
         match direction {
             Dir2::NORTH => {
-                if cell_pos.y == self.rows - 1 {
+                if cell_pos.y == self.rows - 1 && !is_wrapping_vertically {
                     return None;
                 }
             }
             Dir2::SOUTH => {
-                if cell_pos.y == 0 {
+                if cell_pos.y == 0 && !is_wrapping_vertically {
                     return None;
                 }
             }
             Dir2::EAST => {
-                if cell_pos.x == self.columns - 1 {
+                if cell_pos.x == self.columns - 1 && !is_wrapping_horizontally {
                     return None;
                 }
             }
             Dir2::WEST => {
-                if cell_pos.x == 0 {
+                if cell_pos.x == 0 && !is_wrapping_horizontally {
                     return None;
                 }
             }
             _ => return None,
         }
 
-        return self.wall_from_cell_pos(cell_pos, direction);
+        // A wall next to a cell that's turned off is part of the maze's outline.
+        let neighbour_pos = self.wrap_cell_pos(cell_pos + direction.as_ivec2());
+        if !self.is_cell_enabled(cell_pos) || !self.is_cell_enabled(neighbour_pos) {
+            return None;
+        }
+
+        self.wall_from_cell_pos(cell_pos, direction)
+            .map(|wall| self.wrap_wall(wall))
+    }
+
+    // Returns the wall between two neighbouring cells, or `None` if the cells
+    // aren't neighbours.
+    pub fn inner_wall_between_cell_pos(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+        // Cells either side of a seam are a whole row or column apart, so the
+        // direction can't be worked out from their positions.
+        let direction = [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST]
+            .into_iter()
+            .find(|direction| self.neighbour_from_cell_pos(from, *direction) == Some(to))?;
+
+        self.inner_wall_from_cell_pos(from, direction)
+    }
+
+    // Returns the two cells either side of a wall, or `None` for an outer wall.
+    // Seams are only returned for the wall on the west or south edge, so that
+    // each one is only counted once.
+    pub fn cells_from_inner_wall(&self, wall: Wall) -> Option<(IVec2, IVec2)> {
+        if self.wrap_wall(wall) != wall {
+            return None;
+        }
+
+        let (cell_a, cell_b) = self.cells_from_wall(wall);
+        let cells = (self.wrap_cell_pos(cell_a), cell_b);
+
+        match self.is_cell_enabled(cells.0) && self.is_cell_enabled(cells.1) {
+            true => Some(cells),
+            false => None,
+        }
+    }
+
+    // Returns whether a wall should be drawn, which is when there's a cell that's
+    // turned on at least one side of it.
+    pub fn is_wall_next_to_enabled_cell(&self, wall: Wall) -> bool {
+        let cells = self.cells_from_wall(wall);
+        self.is_cell_enabled(cells.0) || self.is_cell_enabled(cells.1)
+    }
+
+    // Returns whether a point should be drawn, which is when one of the cells it's
+    // a corner of is turned on.
+    pub fn is_point_next_to_enabled_cell(&self, point_pos: IVec2) -> bool {
+        [IVec2::ZERO, IVec2::X, IVec2::Y, IVec2::ONE]
+            .into_iter()
+            .any(|offset| self.is_cell_enabled(point_pos - offset))
+    }
+
+    // The cells either side of a wall, which may be outside of the grid map.
+    fn cells_from_wall(&self, wall: Wall) -> (IVec2, IVec2) {
+        if wall.from.y == wall.to.y {
+            // Horizontal, the cells are below and above the wall.
+            (
+                IVec2 {
+                    x: wall.from.x,
+                    y: wall.from.y - 1,
+                },
+                wall.from,
+            )
+        } else {
+            // Vertical, the cells are left and right of the wall.
+            (
+                IVec2 {
+                    x: wall.from.x - 1,
+                    y: wall.from.y,
+                },
+                wall.from,
+            )
+        }
     }
 
     pub fn wall_from_cell_index(&self, index: i32, direction: Dir2) -> Option<Wall> {
@@ -96,14 +296,16 @@ impl GridMap {
     pub fn neighbour_from_cell_index(&self, index: i32, direction: Dir2) -> Option<i32> {
         let cell_pos = self.index_to_cell_pos(index);
         match cell_pos {
-            Some(cell_pos) => self.cell_pos_to_index(cell_pos + direction.as_ivec2()),
+            Some(cell_pos) => self
+                .neighbour_from_cell_pos(cell_pos, direction)
+                .and_then(|neighbour_pos| self.cell_pos_to_index(neighbour_pos)),
             None => None,
         }
     }
 
     pub fn neighbour_from_cell_pos(&self, cell_pos: IVec2, direction: Dir2) -> Option<IVec2> {
-        let neighbour_pos = cell_pos + direction.as_ivec2();
-        match self.is_cell_pos_in_bounds(neighbour_pos) {
+        let neighbour_pos = self.wrap_cell_pos(cell_pos + direction.as_ivec2());
+        match self.is_cell_enabled(neighbour_pos) {
             true => Some(neighbour_pos),
             false => None,
         }
@@ -155,43 +357,13 @@ impl GridMap {
         }
     }
 
+    // Returns `None` for walls that aren't between two neighbouring points.
     pub fn wall_pos_to_index(&self, wall: Wall) -> Option<i32> {
-        if wall.from.x == wall.to.x {
-            // Horizontal.
-            pos_to_index(wall.from, self.columns, self.rows + 1)
-        } else {
-            // Vertical.
-            pos_to_index(wall.from, self.columns + 1, self.rows)
-        }
+        wall_pos_to_index(wall, self.columns, self.rows).map(|(_, index)| index)
     }
 
     pub fn index_to_wall_pos(&self, index: i32, orientation: WallOrientation) -> Option<Wall> {
-        let from = if orientation == WallOrientation::Horizontal {
-            index_to_pos(index, self.columns, self.rows + 1)
-        } else {
-            index_to_pos(index, self.columns + 1, self.rows)
-        };
-
-        // We know that if `from` is valid, then we can also calculate a valid `to` pos
-        // due to the way `index_to_pos` works, so defer calculating `to` until we've
-        // checked there is `Some` `from` value.
-        if let Some(from) = from {
-            let to = if orientation == WallOrientation::Horizontal {
-                IVec2 {
-                    x: from.x + 1,
-                    y: from.y,
-                }
-            } else {
-                IVec2 {
-                    x: from.x,
-                    y: from.y + 1,
-                }
-            };
-
-            return Some(Wall { from, to });
-        } else {
-            return None;
-        }
+        index_to_wall_pos(index, orientation, self.columns, self.rows)
     }
 
     pub fn get_scale_from_available_space(&self, available_space: Vec2) -> f32 {
@@ -214,10 +386,42 @@ impl GridMap {
         -(base_vec * scale) / 2.0
     }
 
+    pub fn get_columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> i32 {
+        self.rows
+    }
+
+    // The number of cells in the whole grid, including any that are turned off.
+    // Cell indexes are always less than this.
     pub fn get_cell_count(&self) -> i32 {
         self.columns * self.rows
     }
 
+    pub fn get_enabled_cell_count(&self) -> i32 {
+        match &self.mask {
+            Some(mask) => mask.get_enabled_count() as i32,
+            None => self.get_cell_count(),
+        }
+    }
+
+    // Returns a random cell that's turned on, or `None` if there aren't any.
+    pub fn random_cell_pos(&self, rng: &mut Rng) -> Option<IVec2> {
+        if self.get_enabled_cell_count() == 0 {
+            return None;
+        }
+
+        loop {
+            let index = rng.usize(0..self.get_cell_count() as usize);
+            let cell_pos = self.index_to_cell_pos(index as i32)?;
+            if self.is_cell_enabled(cell_pos) {
+                return Some(cell_pos);
+            }
+        }
+    }
+
     pub fn get_point_count(&self) -> i32 {
         (self.columns + 1) * (self.rows + 1)
     }
@@ -245,21 +449,21 @@ impl GridMap {
         }
     }
 
-    pub fn iter_cells(&self) -> GridMapCellIterator {
+    pub fn iter_cells(&self) -> GridMapCellIterator<'_> {
         GridMapCellIterator {
             grid_map: self,
             index: 0,
         }
     }
 
-    pub fn iter_points(&self) -> GridMapPointIterator {
+    pub fn iter_points(&self) -> GridMapPointIterator<'_> {
         GridMapPointIterator {
             grid_map: self,
             index: 0,
         }
     }
 
-    pub fn iter_walls(&self, orientation: WallOrientation) -> GridMapWallIterator {
+    pub fn iter_walls(&self, orientation: WallOrientation) -> GridMapWallIterator<'_> {
         GridMapWallIterator {
             grid_map: self,
             index: 0,
@@ -277,9 +481,15 @@ impl<'a> Iterator for GridMapCellIterator<'a> {
     type Item = IVec2;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = self.grid_map.index_to_cell_pos(self.index as i32);
-        self.index += 1;
-        result
+        // Cells that are turned off are skipped.
+        loop {
+            let result = self.grid_map.index_to_cell_pos(self.index as i32);
+            self.index += 1;
+            match result {
+                Some(cell_pos) if !self.grid_map.is_cell_enabled(cell_pos) => continue,
+                _ => return result,
+            }
+        }
     }
 }
 
@@ -320,7 +530,7 @@ fn is_pos_in_bounds(pos: IVec2, columns: i32, rows: i32) -> bool {
     pos.x < columns && pos.x >= 0 && pos.y < rows && pos.y >= 0
 }
 
-fn pos_to_index(pos: IVec2, columns: i32, rows: i32) -> Option<i32> {
+pub(super) fn pos_to_index(pos: IVec2, columns: i32, rows: i32) -> Option<i32> {
     if !is_pos_in_bounds(pos, columns, rows) {
         return None;
     }
@@ -332,7 +542,7 @@ fn is_index_in_bounds(index: i32, columns: i32, rows: i32) -> bool {
     index < (columns * rows)
 }
 
-fn index_to_pos(index: i32, columns: i32, rows: i32) -> Option<IVec2> {
+pub(super) fn index_to_pos(index: i32, columns: i32, rows: i32) -> Option<IVec2> {
     if !is_index_in_bounds(index, columns, rows) {
         return None;
     }
@@ -342,3 +552,186 @@ fn index_to_pos(index: i32, columns: i32, rows: i32) -> Option<IVec2> {
         y: index / columns,
     })
 }
+
+// Horizontal walls are indexed a row of points at a time like cells are, with one
+// fewer wall than points in each row. Vertical walls are the same, with one fewer
+// row of walls than rows of points.
+pub(super) fn wall_pos_to_index(
+    wall: Wall,
+    columns: i32,
+    rows: i32,
+) -> Option<(WallOrientation, i32)> {
+    match wall.to - wall.from {
+        IVec2::X => Some((
+            WallOrientation::Horizontal,
+            pos_to_index(wall.from, columns, rows + 1)?,
+        )),
+        IVec2::Y => Some((
+            WallOrientation::Vertical,
+            pos_to_index(wall.from, columns + 1, rows)?,
+        )),
+        _ => None,
+    }
+}
+
+pub(super) fn index_to_wall_pos(
+    index: i32,
+    orientation: WallOrientation,
+    columns: i32,
+    rows: i32,
+) -> Option<Wall> {
+    let from = match orientation {
+        WallOrientation::Horizontal => index_to_pos(index, columns, rows + 1),
+        WallOrientation::Vertical => index_to_pos(index, columns + 1, rows),
+    }?;
+
+    let to = match orientation {
+        WallOrientation::Horizontal => from + IVec2::X,
+        WallOrientation::Vertical => from + IVec2::Y,
+    };
+
+    Some(Wall { from, to })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_builders::carve_wilson_into_grid;
+    use crate::resources::Grid;
+    use crate::utils::dijkstra;
+
+    const WRAP_MODES: [WrapMode; 3] = [WrapMode::None, WrapMode::Horizontal, WrapMode::Both];
+
+    fn get_wrapped_grid_map(columns: i32, rows: i32, wrap_mode: WrapMode) -> GridMap {
+        let mut grid_map = GridMap::new(columns, rows);
+        grid_map.set_wrap_mode(wrap_mode);
+        grid_map
+    }
+
+    #[test]
+    fn neighbours_wrap_around_the_edges_that_join_up() {
+        for wrap_mode in WRAP_MODES {
+            let grid_map = get_wrapped_grid_map(4, 3, wrap_mode);
+            let west_edge = IVec2::new(0, 1);
+            let south_edge = IVec2::new(1, 0);
+
+            let west_neighbour = grid_map.neighbour_from_cell_pos(west_edge, Dir2::WEST);
+            let south_neighbour = grid_map.neighbour_from_cell_pos(south_edge, Dir2::SOUTH);
+            match wrap_mode {
+                WrapMode::None => {
+                    assert_eq!(west_neighbour, None);
+                    assert_eq!(south_neighbour, None);
+                }
+                WrapMode::Horizontal => {
+                    assert_eq!(west_neighbour, Some(IVec2::new(3, 1)));
+                    assert_eq!(south_neighbour, None);
+                }
+                WrapMode::Both => {
+                    assert_eq!(west_neighbour, Some(IVec2::new(3, 1)));
+                    assert_eq!(south_neighbour, Some(IVec2::new(1, 2)));
+                }
+            }
+
+            // Neighbours are always neighbours of each other, seams included.
+            for cell in grid_map.cells() {
+                for neighbour in grid_map.neighbours(cell) {
+                    assert!(grid_map.neighbours(neighbour).contains(&cell), "{cell}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cells_either_side_of_a_seam_share_the_wall_on_the_west_or_south_edge() {
+        let west_seam = Wall {
+            from: IVec2::new(0, 1),
+            to: IVec2::new(0, 2),
+        };
+        let south_seam = Wall {
+            from: IVec2::new(1, 0),
+            to: IVec2::new(2, 0),
+        };
+
+        for wrap_mode in WRAP_MODES {
+            let grid_map = get_wrapped_grid_map(4, 3, wrap_mode);
+            let west_wall = grid_map.wall_between(IVec2::new(3, 1), IVec2::new(0, 1));
+            let south_wall = grid_map.wall_between(IVec2::new(1, 2), IVec2::new(1, 0));
+            let is_wrapping_vertically = wrap_mode == WrapMode::Both;
+
+            match wrap_mode {
+                WrapMode::None => assert_eq!(west_wall, None),
+                _ => {
+                    assert_eq!(west_wall, Some(west_seam));
+                    assert_eq!(
+                        grid_map.wall_between(IVec2::new(0, 1), IVec2::new(3, 1)),
+                        Some(west_seam)
+                    );
+                }
+            }
+            match is_wrapping_vertically {
+                true => assert_eq!(south_wall, Some(south_seam)),
+                false => assert_eq!(south_wall, None),
+            }
+
+            assert_eq!(
+                grid_map.is_seam_wall(west_seam),
+                wrap_mode != WrapMode::None
+            );
+            assert_eq!(grid_map.is_seam_wall(south_seam), is_wrapping_vertically);
+
+            // The east and north edges are the same seams seen from the other side.
+            let east_wall = Wall {
+                from: IVec2::new(4, 1),
+                to: IVec2::new(4, 2),
+            };
+            let north_wall = Wall {
+                from: IVec2::new(1, 3),
+                to: IVec2::new(2, 3),
+            };
+            let wrapped_east_wall = grid_map.wrap_wall(east_wall);
+            let wrapped_north_wall = grid_map.wrap_wall(north_wall);
+            match wrap_mode {
+                WrapMode::None => assert_eq!(wrapped_east_wall, east_wall),
+                _ => assert_eq!(wrapped_east_wall, west_seam),
+            }
+            match is_wrapping_vertically {
+                true => assert_eq!(wrapped_north_wall, south_seam),
+                false => assert_eq!(wrapped_north_wall, north_wall),
+            }
+        }
+    }
+
+    #[test]
+    fn narrow_grid_maps_dont_wrap() {
+        let grid_map = get_wrapped_grid_map(2, 2, WrapMode::Both);
+        assert!(!grid_map.is_wrapping_horizontally());
+        assert!(!grid_map.is_wrapping_vertically());
+        assert_eq!(grid_map.neighbours(IVec2::ZERO).len(), 2);
+    }
+
+    #[test]
+    fn wrapped_mazes_are_spanning_trees_with_passages_through_the_seams() {
+        for wrap_mode in [WrapMode::Horizontal, WrapMode::Both] {
+            let grid_map = get_wrapped_grid_map(10, 10, wrap_mode);
+            let removed_walls = carve_wilson_into_grid(&grid_map, &mut Rng::with_seed(0));
+            let distances = dijkstra(IVec2::ZERO, &grid_map, &removed_walls);
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+            assert_eq!(distances.len(), grid_map.cell_count());
+
+            // Every passage is a wall between two cells, seams included.
+            assert!(removed_walls
+                .iter()
+                .all(|wall| grid_map.cells_from_inner_wall(wall).is_some()));
+
+            let seams: Vec<Wall> = removed_walls
+                .iter()
+                .filter(|wall| grid_map.is_seam_wall(*wall))
+                .collect();
+            assert!(seams.iter().any(|wall| wall.from.x == 0 && wall.to.x == 0));
+            assert_eq!(
+                seams.iter().any(|wall| wall.from.y == 0 && wall.to.y == 0),
+                wrap_mode == WrapMode::Both
+            );
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use super::{
+    grid_map::index_to_pos, grid_map::pos_to_index, DrawableGrid, Grid, LazyWallIndex, WallSet,
+};
+use crate::components::Wall;
+use bevy::prelude::*;
+use fastrand::Rng;
+
+// The distance from the center of a hexagon to each of its corners, which makes
+// each hexagon one unit tall.
+const HEX_RADIUS: f32 = 0.577_350_26;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum HexDirection {
+    North,
+    NorthEast,
+    SouthEast,
+    South,
+    SouthWest,
+    NorthWest,
+}
+
+impl HexDirection {
+    // Going clockwise from the north.
+    pub const ALL: [HexDirection; 6] = [
+        HexDirection::North,
+        HexDirection::NorthEast,
+        HexDirection::SouthEast,
+        HexDirection::South,
+        HexDirection::SouthWest,
+        HexDirection::NorthWest,
+    ];
+
+    // The two corners of a hexagon that this side is between, see `get_cell_outline`.
+    fn get_corners(&self) -> (usize, usize) {
+        match self {
+            HexDirection::North => (1, 2),
+            HexDirection::NorthEast => (0, 1),
+            HexDirection::SouthEast => (5, 0),
+            HexDirection::South => (4, 5),
+            HexDirection::SouthWest => (3, 4),
+            HexDirection::NorthWest => (2, 3),
+        }
+    }
+}
+
+// A grid of flat topped hexagons, laid out in columns and rows like a `GridMap`.
+// Every odd column is moved half a cell up, so a cell's north east and south
+// east neighbours are in the same row or the one below for even columns, and
+// the same row or the one above for odd columns.
+//
+// Cells are indexed the same way as a `GridMap`, a row at a time from the
+// bottom left.
+#[derive(Resource)]
+pub struct HexGrid {
+    columns: i32,
+    rows: i32,
+    wall_index: LazyWallIndex,
+}
+
+impl HexGrid {
+    pub fn new(columns: i32, rows: i32) -> Self {
+        HexGrid {
+            columns,
+            rows,
+            wall_index: LazyWallIndex::default(),
+        }
+    }
+
+    pub fn get_columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> i32 {
+        self.rows
+    }
+
+    pub fn get_cell_count(&self) -> i32 {
+        self.columns * self.rows
+    }
+
+    pub fn is_cell_pos_in_bounds(&self, cell_pos: IVec2) -> bool {
+        cell_pos.x >= 0 && cell_pos.x < self.columns && cell_pos.y >= 0 && cell_pos.y < self.rows
+    }
+
+    pub fn cell_pos_to_index(&self, cell_pos: IVec2) -> Option<i32> {
+        pos_to_index(cell_pos, self.columns, self.rows)
+    }
+
+    pub fn index_to_cell_pos(&self, index: i32) -> Option<IVec2> {
+        if index < 0 {
+            return None;
+        }
+
+        index_to_pos(index, self.columns, self.rows)
+    }
+
+    pub fn neighbour_from_cell_pos(
+        &self,
+        cell_pos: IVec2,
+        direction: HexDirection,
+    ) -> Option<IVec2> {
+        if !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        // The rows either side of an odd column are half a cell lower.
+        let is_odd_column = cell_pos.x % 2 == 1;
+        let (north_y, south_y) = if is_odd_column { (1, 0) } else { (0, -1) };
+        let offset = match direction {
+            HexDirection::North => IVec2 { x: 0, y: 1 },
+            HexDirection::NorthEast => IVec2 { x: 1, y: north_y },
+            HexDirection::SouthEast => IVec2 { x: 1, y: south_y },
+            HexDirection::South => IVec2 { x: 0, y: -1 },
+            HexDirection::SouthWest => IVec2 { x: -1, y: south_y },
+            HexDirection::NorthWest => IVec2 { x: -1, y: north_y },
+        };
+
+        let neighbour = cell_pos + offset;
+        match self.is_cell_pos_in_bounds(neighbour) {
+            true => Some(neighbour),
+            false => None,
+        }
+    }
+
+    pub fn iter_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.get_cell_count()).filter_map(|index| self.index_to_cell_pos(index))
+    }
+
+    pub fn get_cell_center(&self, cell_pos: IVec2) -> Vec2 {
+        let column_offset = if cell_pos.x % 2 == 1 { 0.5 } else { 0.0 };
+        Vec2 {
+            x: cell_pos.x as f32 * HEX_RADIUS * 1.5,
+            y: cell_pos.y as f32 + column_offset,
+        }
+    }
+
+    // The six corners of the cell, going counter-clockwise from the east.
+    pub fn get_cell_outline(&self, cell_pos: IVec2) -> Vec<Vec2> {
+        let center = self.get_cell_center(cell_pos);
+        (0..6)
+            .map(|corner| {
+                let angle = (corner as f32 * 60.0).to_radians();
+                center + Vec2::from_angle(angle) * HEX_RADIUS
+            })
+            .collect()
+    }
+
+    pub fn get_side_outline(&self, cell_pos: IVec2, direction: HexDirection) -> Vec<Vec2> {
+        let outline = self.get_cell_outline(cell_pos);
+        let (start_corner, end_corner) = direction.get_corners();
+        vec![outline[start_corner], outline[end_corner]]
+    }
+
+    // The size of the grid, and the position of its bottom left corner.
+    fn get_bounds(&self) -> (Vec2, Vec2) {
+        let has_odd_columns = self.columns > 1;
+        let size = Vec2 {
+            x: (self.columns - 1).max(0) as f32 * HEX_RADIUS * 1.5 + HEX_RADIUS * 2.0,
+            y: self.rows as f32 + if has_odd_columns { 0.5 } else { 0.0 },
+        };
+        let bottom_left = Vec2 {
+            x: -HEX_RADIUS,
+            y: -0.5,
+        };
+        (size, bottom_left)
+    }
+}
+
+impl Grid for HexGrid {
+    type Cell = IVec2;
+    type Link = Wall;
+    type LinkSet = WallSet;
+
+    fn cell_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec2> + '_> {
+        Box::new(self.iter_cells())
+    }
+
+    fn cell_index(&self, cell: IVec2) -> Option<usize> {
+        Some(self.cell_pos_to_index(cell)? as usize)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec2> {
+        if self.get_cell_count() <= 0 {
+            return None;
+        }
+
+        self.index_to_cell_pos(rng.i32(0..self.get_cell_count()))
+    }
+
+    fn neighbours(&self, cell: IVec2) -> Vec<IVec2> {
+        HexDirection::ALL
+            .into_iter()
+            .filter_map(|direction| self.neighbour_from_cell_pos(cell, direction))
+            .collect()
+    }
+
+    // The wall is identified by the two cells either side of it, the one with the
+    // lower index comes first.
+    fn wall_between(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+        if !self.neighbours(from).contains(&to) {
+            return None;
+        }
+
+        match self.cell_pos_to_index(from)? < self.cell_pos_to_index(to)? {
+            true => Some(Wall { from, to }),
+            false => Some(Wall { from: to, to: from }),
+        }
+    }
+
+    fn new_link_set(&self) -> WallSet {
+        self.wall_index.new_wall_set(|| self.get_walls())
+    }
+}
+
+impl DrawableGrid for HexGrid {
+    fn get_cell_center(&self, cell: IVec2) -> Vec2 {
+        HexGrid::get_cell_center(self, cell)
+    }
+
+    fn get_cell_outline(&self, cell: IVec2) -> Vec<Vec2> {
+        HexGrid::get_cell_outline(self, cell)
+    }
+
+    fn get_wall_outline(&self, wall: Wall) -> Vec<Vec2> {
+        let direction = HexDirection::ALL
+            .into_iter()
+            .find(|direction| self.neighbour_from_cell_pos(wall.from, *direction) == Some(wall.to));
+
+        match direction {
+            Some(direction) => self.get_side_outline(wall.from, direction),
+            None => vec![],
+        }
+    }
+
+    fn get_boundary_outlines(&self) -> Vec<Vec<Vec2>> {
+        self.iter_cells()
+            .flat_map(|cell_pos| {
+                HexDirection::ALL
+                    .into_iter()
+                    .filter(move |direction| {
+                        self.neighbour_from_cell_pos(cell_pos, *direction).is_none()
+                    })
+                    .map(move |direction| self.get_side_outline(cell_pos, direction))
+            })
+            .collect()
+    }
+
+    fn get_scale_from_available_space(&self, available_space: Vec2) -> f32 {
+        let (size, _) = self.get_bounds();
+        (available_space.x / size.x).min(available_space.y / size.y)
+    }
+
+    fn get_centered_grid_pos(&self, scale: f32) -> Vec2 {
+        let (size, bottom_left) = self.get_bounds();
+        -(bottom_left + size / 2.0) * scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::GridMap;
+
+    #[test]
+    fn even_columns_have_their_eastern_and_western_neighbours_below() {
+        let hex_grid = HexGrid::new(5, 5);
+        assert_eq!(
+            hex_grid.neighbours(IVec2::new(2, 2)),
+            [
+                IVec2::new(2, 3),
+                IVec2::new(3, 2),
+                IVec2::new(3, 1),
+                IVec2::new(2, 1),
+                IVec2::new(1, 1),
+                IVec2::new(1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn odd_columns_have_their_eastern_and_western_neighbours_above() {
+        let hex_grid = HexGrid::new(5, 5);
+        assert_eq!(
+            hex_grid.neighbours(IVec2::new(3, 2)),
+            [
+                IVec2::new(3, 3),
+                IVec2::new(4, 3),
+                IVec2::new(4, 2),
+                IVec2::new(3, 1),
+                IVec2::new(2, 2),
+                IVec2::new(2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn cells_on_the_edges_only_have_neighbours_inside_the_grid() {
+        let hex_grid = HexGrid::new(5, 5);
+        assert_eq!(
+            hex_grid.neighbours(IVec2::new(0, 0)),
+            [IVec2::new(0, 1), IVec2::new(1, 0)]
+        );
+        assert_eq!(
+            hex_grid.neighbours(IVec2::new(4, 4)),
+            [IVec2::new(4, 3), IVec2::new(3, 3), IVec2::new(3, 4)]
+        );
+        assert_eq!(
+            hex_grid.neighbours(IVec2::new(1, 0)),
+            [
+                IVec2::new(1, 1),
+                IVec2::new(2, 1),
+                IVec2::new(2, 0),
+                IVec2::new(0, 0),
+                IVec2::new(0, 1),
+            ]
+        );
+        assert_eq!(
+            hex_grid.neighbours(IVec2::new(3, 4)),
+            [IVec2::new(4, 4), IVec2::new(3, 3), IVec2::new(2, 4)]
+        );
+        assert_eq!(hex_grid.neighbours(IVec2::new(5, 0)), []);
+    }
+
+    #[test]
+    fn neighbours_share_the_same_wall_from_either_side() {
+        let hex_grid = HexGrid::new(6, 5);
+        for cell in hex_grid.cells() {
+            for neighbour in hex_grid.neighbours(cell) {
+                assert!(hex_grid.neighbours(neighbour).contains(&cell), "{cell}");
+
+                let wall = hex_grid.wall_between(cell, neighbour);
+                assert!(wall.is_some(), "{cell}");
+                assert_eq!(wall, hex_grid.wall_between(neighbour, cell), "{cell}");
+            }
+        }
+        assert_eq!(
+            hex_grid.wall_between(IVec2::new(0, 0), IVec2::new(1, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn cells_are_indexed_like_a_grid_map() {
+        let hex_grid = HexGrid::new(6, 5);
+        let grid_map = GridMap::new(6, 5);
+        for cell in hex_grid.cells() {
+            assert_eq!(
+                hex_grid.cell_pos_to_index(cell),
+                grid_map.cell_pos_to_index(cell)
+            );
+        }
+        assert!(hex_grid.cells().eq(grid_map.cells()));
+        assert_eq!(hex_grid.cell_index_count(), grid_map.cell_index_count());
+    }
+}
@@ -0,0 +1,226 @@
+use super::{Grid, GridMap, WallSet};
+use crate::components::Wall;
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+
+// The link between two neighbouring cells of a `LayeredGrid`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LayeredLink {
+    // A wall between two cells on the same level.
+    Wall { level: i32, wall: Wall },
+    // The floor between a cell and the cell directly above it, identified by the
+    // lower cell. Removing it puts in a staircase.
+    Stairs(IVec3),
+}
+
+// A stack of levels that all have the same layout as a `GridMap`. Cells are an
+// `IVec3`, where z is the level counting up from 0, and each cell is also a
+// neighbour of the cells directly above and below it.
+#[derive(Resource)]
+pub struct LayeredGrid {
+    grid_map: GridMap,
+    levels: i32,
+}
+
+impl LayeredGrid {
+    pub fn new(columns: i32, rows: i32, levels: i32) -> Self {
+        LayeredGrid {
+            grid_map: GridMap::new(columns, rows),
+            levels,
+        }
+    }
+
+    // The layout of each level.
+    pub fn get_grid_map(&self) -> &GridMap {
+        &self.grid_map
+    }
+
+    pub fn get_levels(&self) -> i32 {
+        self.levels
+    }
+
+    pub fn is_cell_enabled(&self, cell: IVec3) -> bool {
+        (0..self.levels).contains(&cell.z) && self.grid_map.is_cell_enabled(cell.xy())
+    }
+
+    // The walls that have been removed on one level, as walls of its grid map.
+    pub fn get_level_walls(&self, removed_links: &HashSet<LayeredLink>, level: i32) -> WallSet {
+        let mut level_walls = self.grid_map.new_link_set();
+        level_walls.extend(removed_links.iter().filter_map(|link| match link {
+            LayeredLink::Wall {
+                level: wall_level,
+                wall,
+            } if *wall_level == level => Some(*wall),
+            _ => None,
+        }));
+        level_walls
+    }
+}
+
+impl Grid for LayeredGrid {
+    type Cell = IVec3;
+    type Link = LayeredLink;
+    type LinkSet = HashSet<LayeredLink>;
+
+    fn cell_count(&self) -> usize {
+        self.grid_map.cell_count() * self.levels.max(0) as usize
+    }
+
+    // A level at a time from the bottom, each one in the grid map's order.
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec3> + '_> {
+        Box::new((0..self.levels).flat_map(|level| {
+            self.grid_map
+                .iter_cells()
+                .map(move |cell_pos| cell_pos.extend(level))
+        }))
+    }
+
+    // Each level's cells come after the level below's.
+    fn cell_index(&self, cell: IVec3) -> Option<usize> {
+        if !(0..self.levels).contains(&cell.z) {
+            return None;
+        }
+
+        let level_index = self.grid_map.cell_index(cell.xy())?;
+        Some(cell.z as usize * self.grid_map.cell_index_count() + level_index)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.grid_map.cell_index_count() * self.levels.max(0) as usize
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec3> {
+        if self.levels <= 0 {
+            return None;
+        }
+
+        let level = rng.i32(0..self.levels);
+        Some(self.grid_map.random_cell_pos(rng)?.extend(level))
+    }
+
+    // The neighbours on the same level, then the cells above and below.
+    fn neighbours(&self, cell: IVec3) -> Vec<IVec3> {
+        let mut neighbours: Vec<IVec3> = self
+            .grid_map
+            .neighbours(cell.xy())
+            .into_iter()
+            .map(|cell_pos| cell_pos.extend(cell.z))
+            .collect();
+        neighbours.extend(
+            [cell + IVec3::Z, cell - IVec3::Z]
+                .into_iter()
+                .filter(|neighbour| self.is_cell_enabled(*neighbour)),
+        );
+        neighbours
+    }
+
+    fn wall_between(&self, from: IVec3, to: IVec3) -> Option<LayeredLink> {
+        if !self.is_cell_enabled(from) || !self.is_cell_enabled(to) {
+            return None;
+        }
+
+        if from.z == to.z {
+            let wall = self.grid_map.wall_between(from.xy(), to.xy())?;
+            return Some(LayeredLink::Wall {
+                level: from.z,
+                wall,
+            });
+        }
+
+        match from.xy() == to.xy() && (from.z - to.z).abs() == 1 {
+            true => Some(LayeredLink::Stairs(from.min(to))),
+            false => None,
+        }
+    }
+
+    fn new_link_set(&self) -> HashSet<LayeredLink> {
+        HashSet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::MazeBuilderRegistry;
+    use crate::utils::dijkstra;
+
+    #[test]
+    fn stairs_join_cells_directly_above_and_below() {
+        let layered_grid = LayeredGrid::new(3, 3, 3);
+        let bottom = IVec3::new(1, 1, 0);
+        let middle = IVec3::new(1, 1, 1);
+        let top = IVec3::new(1, 1, 2);
+
+        assert!(layered_grid.neighbours(bottom).contains(&middle));
+        assert!(!layered_grid
+            .neighbours(bottom)
+            .contains(&(bottom - IVec3::Z)));
+        assert!(!layered_grid.neighbours(top).contains(&(top + IVec3::Z)));
+
+        // The stairs are identified by the lower cell, whichever way they're found.
+        assert_eq!(
+            layered_grid.wall_between(middle, bottom),
+            Some(LayeredLink::Stairs(bottom))
+        );
+        assert_eq!(
+            layered_grid.wall_between(middle, top),
+            Some(LayeredLink::Stairs(middle))
+        );
+        assert_eq!(layered_grid.wall_between(bottom, top), None);
+        assert_eq!(
+            layered_grid.wall_between(IVec3::ZERO, IVec3::new(1, 0, 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn links_are_the_same_from_either_side() {
+        let layered_grid = LayeredGrid::new(4, 3, 3);
+        for cell in layered_grid.cells() {
+            for neighbour in layered_grid.neighbours(cell) {
+                assert!(layered_grid.neighbours(neighbour).contains(&cell));
+                assert!(layered_grid.wall_between(cell, neighbour).is_some());
+                assert_eq!(
+                    layered_grid.wall_between(cell, neighbour),
+                    layered_grid.wall_between(neighbour, cell)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_layered_builder_carves_a_spanning_tree_across_the_levels() {
+        let layered_grid = LayeredGrid::new(6, 5, 3);
+        let mut builder_count = 0;
+
+        for builder in MazeBuilderRegistry::default().iter() {
+            let mut rng = Rng::with_seed(0);
+            let Some(removed_links) = builder.carve_layered_grid(&layered_grid, &mut rng) else {
+                continue;
+            };
+            builder_count += 1;
+
+            let name = builder.name();
+            let distances = dijkstra(IVec3::ZERO, &layered_grid, &removed_links);
+            assert_eq!(removed_links.len(), layered_grid.cell_count() - 1, "{name}");
+            assert_eq!(distances.len(), layered_grid.cell_count(), "{name}");
+
+            // Each level's walls only come from the links on that level.
+            let level_wall_count: usize = (0..layered_grid.get_levels())
+                .map(|level| layered_grid.get_level_walls(&removed_links, level).len())
+                .sum();
+            let stairs_count = removed_links
+                .iter()
+                .filter(|link| matches!(link, LayeredLink::Stairs(_)))
+                .count();
+            assert!(stairs_count >= 2, "{name}");
+            assert_eq!(
+                level_wall_count + stairs_count,
+                removed_links.len(),
+                "{name}"
+            );
+        }
+
+        assert!(builder_count > 0);
+    }
+}
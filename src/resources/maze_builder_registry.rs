@@ -0,0 +1,246 @@
+use crate::maze_builders::*;
+use bevy::prelude::*;
+
+#[derive(Resource)]
+pub struct MazeBuilderRegistry {
+    builders: Vec<Box<dyn MazeBuilder>>,
+}
+
+impl MazeBuilderRegistry {
+    pub fn new() -> Self {
+        MazeBuilderRegistry { builders: vec![] }
+    }
+
+    // Builders are listed in the order they're registered. Registering a builder
+    // with the same name as an existing one replaces it in place.
+    pub fn register(&mut self, builder: impl MazeBuilder + 'static) {
+        let existing = self
+            .builders
+            .iter()
+            .position(|registered| registered.name() == builder.name());
+
+        match existing {
+            Some(index) => self.builders[index] = Box::new(builder),
+            None => self.builders.push(Box::new(builder)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn MazeBuilder> {
+        self.builders
+            .iter()
+            .find(|builder| builder.name() == name)
+            .map(|builder| builder.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn MazeBuilder> {
+        self.builders.iter().map(|builder| builder.as_ref())
+    }
+}
+
+impl Default for MazeBuilderRegistry {
+    fn default() -> Self {
+        let mut registry = MazeBuilderRegistry::new();
+        registry.register(BinaryTree::default());
+        registry.register(Sidewinder::default());
+        registry.register(AldousBroder);
+        registry.register(Wilson);
+        registry.register(Houston::default());
+        registry.register(RecursiveBacktracker);
+        registry.register(HuntAndKill);
+        registry.register(Kruskal::default());
+        registry.register(SimplifiedPrims);
+        registry.register(TruePrims);
+        registry.register(GrowingTree::default());
+        registry.register(Ellers);
+        registry.register(RecursiveDivision::default());
+        registry.register(OriginShift::default());
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Wall;
+    use crate::maze_post_processors::get_dead_ends;
+    use crate::resources::{
+        Grid, GridMap, HexGrid, LayeredGrid, PolarGrid, TriangleGrid, UpsilonGrid, WallSet,
+        WeaveGrid,
+    };
+    use crate::utils::dijkstra;
+    use fastrand::Rng;
+
+    // A perfect maze is a spanning tree, so it removes one wall fewer than there
+    // are cells and every cell can be reached from every other.
+    fn assert_spanning_tree(
+        name: &str,
+        grid: &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>,
+        removed_walls: &WallSet,
+    ) {
+        let start = grid.cells().next().unwrap();
+        let distances = dijkstra(start, grid, removed_walls);
+        assert_eq!(removed_walls.len(), grid.cell_count() - 1, "{name}");
+        assert_eq!(distances.len(), grid.cell_count(), "{name}");
+    }
+
+    #[test]
+    fn every_builder_carves_a_spanning_tree() {
+        let grid_map = GridMap::new(12, 9);
+        let polar_grid = PolarGrid::new(5);
+        let hex_grid = HexGrid::new(7, 6);
+        let triangle_grid = TriangleGrid::new(9, 6).unwrap();
+        let upsilon_grid = UpsilonGrid::new(7, 6);
+        let grids: [&dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>; 4] =
+            [&polar_grid, &hex_grid, &triangle_grid, &upsilon_grid];
+        let layered_grid = LayeredGrid::new(5, 4, 3);
+        let weave_grid = WeaveGrid::new(8, 7);
+
+        for builder in MazeBuilderRegistry::default().iter() {
+            let name = builder.name();
+            for seed in 0..5 {
+                let mut rng = Rng::with_seed(seed);
+                let removed_walls = builder.carve(&grid_map, &mut rng);
+                assert_spanning_tree(name, &grid_map, &removed_walls);
+
+                for grid in grids {
+                    if let Some(removed_walls) = builder.carve_grid(grid, &mut rng) {
+                        assert_spanning_tree(name, grid, &removed_walls);
+                    }
+                }
+
+                if let Some(removed_links) = builder.carve_layered_grid(&layered_grid, &mut rng) {
+                    let distances = dijkstra(IVec3::ZERO, &layered_grid, &removed_links);
+                    assert_eq!(removed_links.len(), layered_grid.cell_count() - 1, "{name}");
+                    assert_eq!(distances.len(), layered_grid.cell_count(), "{name}");
+                }
+
+                // The tunnels under cells are cells of their own.
+                if let Some(removed_links) = builder.carve_weave_grid(&weave_grid, &mut rng) {
+                    let cell_count =
+                        weave_grid.cell_count() + weave_grid.get_under_cells(&removed_links).len();
+                    let distances = dijkstra(IVec3::ZERO, &weave_grid, &removed_links);
+                    assert_eq!(removed_links.len(), cell_count - 1, "{name}");
+                    assert_eq!(distances.len(), cell_count, "{name}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn every_builder_carves_the_same_maze_from_the_same_seed() {
+        let grid_map = GridMap::new(16, 12);
+        let hex_grid = HexGrid::new(7, 6);
+        let layered_grid = LayeredGrid::new(5, 4, 3);
+        let weave_grid = WeaveGrid::new(8, 7);
+
+        for builder in MazeBuilderRegistry::default().iter() {
+            let name = builder.name();
+            let removed_walls = builder.carve(&grid_map, &mut Rng::with_seed(3));
+            let same_walls = builder.carve(&grid_map, &mut Rng::with_seed(3));
+            let other_walls = builder.carve(&grid_map, &mut Rng::with_seed(4));
+            assert!(removed_walls == same_walls, "{name}");
+            assert!(removed_walls != other_walls, "{name}");
+
+            assert!(
+                builder.carve_grid(&hex_grid, &mut Rng::with_seed(3))
+                    == builder.carve_grid(&hex_grid, &mut Rng::with_seed(3)),
+                "{name}"
+            );
+            assert!(
+                builder.carve_layered_grid(&layered_grid, &mut Rng::with_seed(3))
+                    == builder.carve_layered_grid(&layered_grid, &mut Rng::with_seed(3)),
+                "{name}"
+            );
+            assert!(
+                builder.carve_weave_grid(&weave_grid, &mut Rng::with_seed(3))
+                    == builder.carve_weave_grid(&weave_grid, &mut Rng::with_seed(3)),
+                "{name}"
+            );
+        }
+    }
+
+    // Walks that only step into unvisited cells make long corridors with few dead
+    // ends, while growing the maze outwards from all of its edges at once leaves
+    // lots of short branches.
+    #[test]
+    fn builders_leave_as_many_dead_ends_as_they_describe() {
+        let grid_map = GridMap::new(30, 30);
+        let registry = MazeBuilderRegistry::default();
+        let get_dead_end_count = |name: &str, seed| {
+            let builder = registry.get(name).unwrap();
+            let removed_walls = builder.carve(&grid_map, &mut Rng::with_seed(seed));
+            get_dead_ends(&grid_map, &removed_walls).len()
+        };
+
+        for seed in 0..5 {
+            let wilson_dead_ends = get_dead_end_count("Wilson", seed);
+            let backtracker_dead_ends = get_dead_end_count("Recursive Backtracker", seed);
+            assert!(backtracker_dead_ends * 2 < wilson_dead_ends);
+            assert!(get_dead_end_count("Hunt and Kill", seed) * 2 < wilson_dead_ends);
+            for name in ["Simplified Prim's", "True Prim's"] {
+                assert!(
+                    get_dead_end_count(name, seed) > backtracker_dead_ends * 2,
+                    "{name}"
+                );
+            }
+        }
+    }
+
+    // A builder like one registered from outside the crate, which only removes the
+    // first wall it finds.
+    struct FirstWall(&'static str);
+
+    impl MazeBuilder for FirstWall {
+        fn name(&self) -> &str {
+            "First Wall"
+        }
+
+        fn description(&self) -> &str {
+            self.0
+        }
+
+        fn carve(&self, grid_map: &GridMap, _rng: &mut Rng) -> WallSet {
+            let mut removed_walls = grid_map.new_link_set();
+            let cell_pos = grid_map.cells().next().unwrap();
+            removed_walls.extend(
+                grid_map
+                    .neighbour_walls(cell_pos)
+                    .into_iter()
+                    .map(|(_, wall)| wall)
+                    .take(1),
+            );
+            removed_walls
+        }
+    }
+
+    #[test]
+    fn builders_are_found_by_name_in_the_order_they_were_registered() {
+        let mut registry = MazeBuilderRegistry::new();
+        registry.register(Wilson);
+        registry.register(FirstWall("first"));
+
+        let names: Vec<&str> = registry.iter().map(|builder| builder.name()).collect();
+        assert_eq!(names, ["Wilson", "First Wall"]);
+        assert!(registry.get("Wilson").is_some());
+        assert!(registry.get("Dijkstra's").is_none());
+
+        let grid_map = GridMap::new(4, 3);
+        let removed_walls = registry
+            .get("First Wall")
+            .unwrap()
+            .carve(&grid_map, &mut Rng::with_seed(0));
+        assert_eq!(removed_walls.len(), 1);
+    }
+
+    #[test]
+    fn registering_the_same_name_replaces_the_builder_in_place() {
+        let mut registry = MazeBuilderRegistry::new();
+        registry.register(FirstWall("first"));
+        registry.register(Wilson);
+        registry.register(FirstWall("second"));
+
+        let names: Vec<&str> = registry.iter().map(|builder| builder.name()).collect();
+        assert_eq!(names, ["First Wall", "Wilson"]);
+        assert_eq!(registry.get("First Wall").unwrap().description(), "second");
+    }
+}
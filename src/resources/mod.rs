@@ -1,37 +1,110 @@
-use crate::components::Wall;
+use crate::utils::Distances;
 use bevy::{
     prelude::*,
     utils::{HashMap, HashSet},
 };
+use std::hash::Hash;
+mod cell_mask;
+mod grid;
 mod grid_map;
+mod hex_grid;
+mod layered_grid;
+mod maze_builder_registry;
+mod polar_grid;
+mod triangle_grid;
+mod upsilon_grid;
+mod wall_set;
+mod weave_grid;
 
+pub use cell_mask::*;
+pub use grid::*;
 pub use grid_map::*;
+pub use hex_grid::*;
+pub use layered_grid::*;
+pub use maze_builder_registry::*;
+pub use polar_grid::*;
+pub use triangle_grid::*;
+pub use upsilon_grid::*;
+pub use wall_set::*;
+pub use weave_grid::*;
 
 #[derive(Resource)]
 pub struct RngSeed(pub u64);
 
+// Which grid the maze is carved into, the `GridMap`, `PolarGrid`, `HexGrid`,
+// `TriangleGrid`, `UpsilonGrid`, `LayeredGrid` or `WeaveGrid`.
+#[derive(Resource, Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum GridType {
+    #[default]
+    Square,
+    Polar,
+    Hex,
+    Triangle,
+    Upsilon,
+    Layered,
+    Weave,
+}
+
+// The name of the builder in the `MazeBuilderRegistry` used to carve the maze.
+#[derive(Resource)]
+pub struct SelectedMazeBuilder(pub String);
+
+// Slowly changes the current maze over time, see `OriginShiftMaze`.
 #[derive(Resource)]
-pub struct OverlayState(pub bool);
-
-#[derive(Resource, Copy, Clone)]
-pub enum MazeBuilderType {
-    BinaryTree,
-    Sidewinder,
-    AldousBroder,
-    Wilson,
+pub struct LivingMaze {
+    pub is_enabled: bool,
+    pub frames_per_shift: u32,
+}
+
+impl Default for LivingMaze {
+    fn default() -> Self {
+        LivingMaze {
+            is_enabled: false,
+            frames_per_shift: 10,
+        }
+    }
 }
 
 #[derive(Resource)]
-pub struct RemovedWalls(pub HashSet<Wall>);
+pub struct RemovedWalls(pub WallSet);
+
+// Cells that have been filled in, so they aren't part of the maze.
+#[derive(Resource, Default)]
+pub struct InaccessibleCells(pub HashSet<IVec2>);
 
 #[derive(Resource)]
 pub struct DijkstraMap(pub HashMap<IVec2, i32>);
 
+// The cells are an `IVec2` for every grid except the `LayeredGrid`.
 #[derive(Resource, Default)]
-pub struct Solution {
-    pub start: IVec2,
-    pub end: IVec2,
-    pub path: HashMap<IVec2, i32>,
-    pub distances: HashMap<IVec2, i32>,
+pub struct Solution<C: Copy + Eq + Hash + Send + Sync + 'static = IVec2> {
+    pub start: C,
+    pub end: C,
+    pub path: HashMap<C, i32>,
+    // Looking a cell up needs the grid the maze was solved on, see `Distances`.
+    pub distances: Distances<C>,
     pub farthest_distance: i32,
 }
+
+// The maze carved into the `LayeredGrid`. Only one level is shown at a time, by
+// copying that level's walls, cells and solution into the `RemovedWalls`,
+// `InaccessibleCells` and `Solution` resources.
+#[derive(Resource, Default)]
+pub struct LayeredMaze {
+    pub removed_links: HashSet<LayeredLink>,
+    pub inaccessible_cells: HashSet<IVec3>,
+    pub solution: Solution<IVec3>,
+}
+
+// The maze carved into the `WeaveGrid`. It's drawn as a square grid map, with the
+// walls that passages go through in `RemovedWalls`, and the cells on top in
+// `Solution`.
+#[derive(Resource, Default)]
+pub struct WeaveMaze {
+    pub removed_links: HashSet<WeaveLink>,
+    pub solution: Solution<IVec3>,
+}
+
+// The level of the `LayeredGrid` that's shown, counting up from 0.
+#[derive(Resource, Default)]
+pub struct VisibleLevel(pub i32);
@@ -0,0 +1,436 @@
+use super::{DrawableGrid, Grid, LazyWallIndex, WallSet};
+use crate::components::Wall;
+use bevy::prelude::*;
+use fastrand::Rng;
+use std::f32::consts::TAU;
+
+// How many straight segments are used per unit of arc length when drawing arcs.
+const ARC_SEGMENTS_PER_UNIT: f32 = 3.0;
+
+// A circular grid made of rings of cells around a single cell in the middle.
+// Rings are split into more cells as they move outward, so that cells stay
+// roughly as wide as they are tall.
+//
+// A cell's position is its index around the ring as `x`, counter-clockwise from
+// the east, and its ring as `y`. Each ring is one unit thick, so the grid's
+// radius is its number of rings.
+#[derive(Resource)]
+pub struct PolarGrid {
+    ring_cell_counts: Vec<i32>,
+    // The index of the first cell of each ring, counting outwards.
+    ring_offsets: Vec<i32>,
+    wall_index: LazyWallIndex,
+}
+
+impl PolarGrid {
+    pub fn new(rings: i32) -> Self {
+        let mut ring_cell_counts: Vec<i32> = vec![];
+        for ring in 0..rings.max(0) {
+            let cell_count = match ring_cell_counts.last() {
+                None => 1,
+                Some(previous_count) => {
+                    // Split each cell of the previous ring into however many cells
+                    // keep the new ring's cells closest to square.
+                    let circumference = TAU * ring as f32;
+                    let estimated_width = circumference / *previous_count as f32;
+                    let ratio = estimated_width.round().max(1.0) as i32;
+                    previous_count * ratio
+                }
+            };
+            ring_cell_counts.push(cell_count);
+        }
+
+        let ring_offsets = ring_cell_counts
+            .iter()
+            .scan(0, |offset, count| {
+                let ring_offset = *offset;
+                *offset += count;
+                Some(ring_offset)
+            })
+            .collect();
+
+        PolarGrid {
+            ring_cell_counts,
+            ring_offsets,
+            wall_index: LazyWallIndex::default(),
+        }
+    }
+
+    pub fn get_rings(&self) -> i32 {
+        self.ring_cell_counts.len() as i32
+    }
+
+    // Returns 0 for rings outside of the grid.
+    pub fn get_ring_cell_count(&self, ring: i32) -> i32 {
+        if ring < 0 {
+            return 0;
+        }
+
+        self.ring_cell_counts
+            .get(ring as usize)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    pub fn get_cell_count(&self) -> i32 {
+        self.ring_cell_counts.iter().sum()
+    }
+
+    pub fn is_cell_pos_in_bounds(&self, cell_pos: IVec2) -> bool {
+        cell_pos.x >= 0 && cell_pos.x < self.get_ring_cell_count(cell_pos.y)
+    }
+
+    pub fn cell_pos_to_index(&self, cell_pos: IVec2) -> Option<i32> {
+        if !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        Some(self.ring_offsets[cell_pos.y as usize] + cell_pos.x)
+    }
+
+    pub fn index_to_cell_pos(&self, index: i32) -> Option<IVec2> {
+        if index < 0 {
+            return None;
+        }
+
+        let ring = self
+            .ring_offsets
+            .iter()
+            .rposition(|offset| *offset <= index)?;
+        let cell_pos = IVec2 {
+            x: index - self.ring_offsets[ring],
+            y: ring as i32,
+        };
+
+        match self.is_cell_pos_in_bounds(cell_pos) {
+            true => Some(cell_pos),
+            false => None,
+        }
+    }
+
+    // The cell in the next ring in that this cell sits against.
+    pub fn inward(&self, cell_pos: IVec2) -> Option<IVec2> {
+        if cell_pos.y <= 0 || !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        let ratio = self.get_ring_cell_count(cell_pos.y) / self.get_ring_cell_count(cell_pos.y - 1);
+        Some(IVec2 {
+            x: cell_pos.x / ratio,
+            y: cell_pos.y - 1,
+        })
+    }
+
+    // The cells in the next ring out that sit against this cell, there's more than
+    // one where the next ring is split.
+    pub fn outward(&self, cell_pos: IVec2) -> Vec<IVec2> {
+        let outer_count = self.get_ring_cell_count(cell_pos.y + 1);
+        if outer_count == 0 || !self.is_cell_pos_in_bounds(cell_pos) {
+            return vec![];
+        }
+
+        let ratio = outer_count / self.get_ring_cell_count(cell_pos.y);
+        (cell_pos.x * ratio..(cell_pos.x + 1) * ratio)
+            .map(|x| IVec2 {
+                x,
+                y: cell_pos.y + 1,
+            })
+            .collect()
+    }
+
+    pub fn clockwise(&self, cell_pos: IVec2) -> Option<IVec2> {
+        let count = self.get_ring_cell_count(cell_pos.y);
+        if count <= 1 || !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        Some(IVec2 {
+            x: (cell_pos.x + count - 1) % count,
+            y: cell_pos.y,
+        })
+    }
+
+    pub fn counter_clockwise(&self, cell_pos: IVec2) -> Option<IVec2> {
+        let count = self.get_ring_cell_count(cell_pos.y);
+        if count <= 1 || !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        Some(IVec2 {
+            x: (cell_pos.x + 1) % count,
+            y: cell_pos.y,
+        })
+    }
+
+    pub fn iter_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.ring_cell_counts
+            .iter()
+            .enumerate()
+            .flat_map(|(ring, count)| (0..*count).map(move |x| IVec2 { x, y: ring as i32 }))
+    }
+
+    // Every wall between two cells, each one only once.
+    pub fn iter_walls(&self) -> impl Iterator<Item = Wall> + '_ {
+        self.iter_cells().flat_map(move |cell_pos| {
+            self.neighbour_walls(cell_pos)
+                .into_iter()
+                .map(|(_, wall)| wall)
+                .filter(move |wall| wall.from == cell_pos)
+        })
+    }
+
+    pub fn get_scale_from_available_space(&self, available_space: Vec2) -> f32 {
+        let diameter = (self.get_rings() * 2).max(1) as f32;
+        available_space.x.min(available_space.y) / diameter
+    }
+
+    // The angles, in radians, of the clockwise and counter-clockwise edges of
+    // a cell.
+    fn get_cell_angles(&self, cell_pos: IVec2) -> (f32, f32) {
+        let angle = TAU / self.get_ring_cell_count(cell_pos.y).max(1) as f32;
+        (cell_pos.x as f32 * angle, (cell_pos.x + 1) as f32 * angle)
+    }
+
+    pub fn get_cell_center(&self, cell_pos: IVec2) -> Vec2 {
+        if cell_pos.y == 0 {
+            return Vec2::ZERO;
+        }
+
+        let (start_angle, end_angle) = self.get_cell_angles(cell_pos);
+        let radius = cell_pos.y as f32 + 0.5;
+        Vec2::from_angle((start_angle + end_angle) / 2.0) * radius
+    }
+
+    // The corners of the cell, going counter-clockwise, with extra points along
+    // the curved edges.
+    pub fn get_cell_outline(&self, cell_pos: IVec2) -> Vec<Vec2> {
+        let (start_angle, end_angle) = self.get_cell_angles(cell_pos);
+        let inner_radius = cell_pos.y as f32;
+        let outer_radius = inner_radius + 1.0;
+
+        let mut outline = get_arc_points(outer_radius, start_angle, end_angle);
+        if cell_pos.y > 0 {
+            let mut inner_arc = get_arc_points(inner_radius, start_angle, end_angle);
+            inner_arc.reverse();
+            outline.append(&mut inner_arc);
+        }
+        outline
+    }
+
+    // The points along a wall between two cells, either a straight line between
+    // cells in the same ring, or an arc between rings.
+    pub fn get_wall_outline(&self, wall: Wall) -> Vec<Vec2> {
+        let (cell_a, cell_b) = (wall.from, wall.to);
+        if cell_a.y == cell_b.y {
+            let angle = if self.counter_clockwise(cell_a) == Some(cell_b) {
+                self.get_cell_angles(cell_a).1
+            } else {
+                self.get_cell_angles(cell_b).1
+            };
+            let direction = Vec2::from_angle(angle);
+            return vec![
+                direction * cell_a.y as f32,
+                direction * (cell_a.y + 1) as f32,
+            ];
+        }
+
+        // The outer cell is never wider than the inner one, so the wall is the
+        // outer cell's inner edge.
+        let outer_cell = if cell_a.y > cell_b.y { cell_a } else { cell_b };
+        let (start_angle, end_angle) = self.get_cell_angles(outer_cell);
+        get_arc_points(outer_cell.y as f32, start_angle, end_angle)
+    }
+
+    // The points around the outside of the grid.
+    pub fn get_outer_wall_outline(&self) -> Vec<Vec2> {
+        get_arc_points(self.get_rings() as f32, 0.0, TAU)
+    }
+}
+
+fn get_arc_points(radius: f32, start_angle: f32, end_angle: f32) -> Vec<Vec2> {
+    let segments = ((end_angle - start_angle) * radius * ARC_SEGMENTS_PER_UNIT)
+        .ceil()
+        .max(1.0) as i32;
+
+    (0..=segments)
+        .map(|segment| {
+            let angle = start_angle + (end_angle - start_angle) * segment as f32 / segments as f32;
+            Vec2::from_angle(angle) * radius
+        })
+        .collect()
+}
+
+impl Grid for PolarGrid {
+    type Cell = IVec2;
+    type Link = Wall;
+    type LinkSet = WallSet;
+
+    fn cell_count(&self) -> usize {
+        self.get_cell_count() as usize
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec2> + '_> {
+        Box::new(self.iter_cells())
+    }
+
+    fn cell_index(&self, cell: IVec2) -> Option<usize> {
+        Some(self.cell_pos_to_index(cell)? as usize)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec2> {
+        if self.get_cell_count() == 0 {
+            return None;
+        }
+
+        self.index_to_cell_pos(rng.i32(0..self.get_cell_count()))
+    }
+
+    fn neighbours(&self, cell: IVec2) -> Vec<IVec2> {
+        let mut neighbours: Vec<IVec2> = self
+            .inward(cell)
+            .into_iter()
+            .chain(self.clockwise(cell))
+            .chain(self.counter_clockwise(cell))
+            .chain(self.outward(cell))
+            .collect();
+        neighbours.dedup();
+        neighbours
+    }
+
+    // The wall is identified by the two cells either side of it, the one that's
+    // further in, or first around the ring, comes first.
+    fn wall_between(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+        if !self.neighbours(from).contains(&to) {
+            return None;
+        }
+
+        let from_index = self.cell_pos_to_index(from)?;
+        let to_index = self.cell_pos_to_index(to)?;
+        match from_index < to_index {
+            true => Some(Wall { from, to }),
+            false => Some(Wall { from: to, to: from }),
+        }
+    }
+
+    fn new_link_set(&self) -> WallSet {
+        self.wall_index.new_wall_set(|| self.get_walls())
+    }
+}
+
+impl DrawableGrid for PolarGrid {
+    fn get_cell_center(&self, cell: IVec2) -> Vec2 {
+        PolarGrid::get_cell_center(self, cell)
+    }
+
+    fn get_cell_outline(&self, cell: IVec2) -> Vec<Vec2> {
+        PolarGrid::get_cell_outline(self, cell)
+    }
+
+    fn get_wall_outline(&self, wall: Wall) -> Vec<Vec2> {
+        PolarGrid::get_wall_outline(self, wall)
+    }
+
+    fn get_boundary_outlines(&self) -> Vec<Vec<Vec2>> {
+        vec![self.get_outer_wall_outline()]
+    }
+
+    fn get_scale_from_available_space(&self, available_space: Vec2) -> f32 {
+        PolarGrid::get_scale_from_available_space(self, available_space)
+    }
+
+    // The grid is drawn around its middle cell, so it doesn't need moving.
+    fn get_centered_grid_pos(&self, _scale: f32) -> Vec2 {
+        Vec2::ZERO
+    }
+
+    fn get_walls(&self) -> Vec<Wall> {
+        self.iter_walls().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rings_split_as_they_move_outward() {
+        let polar_grid = PolarGrid::new(8);
+        let ring_cell_counts: Vec<i32> = (0..8)
+            .map(|ring| polar_grid.get_ring_cell_count(ring))
+            .collect();
+        assert_eq!(ring_cell_counts, [1, 6, 12, 24, 24, 24, 48, 48]);
+        assert_eq!(polar_grid.get_cell_count(), 187);
+        assert_eq!(polar_grid.get_ring_cell_count(8), 0);
+        assert_eq!(polar_grid.get_ring_cell_count(-1), 0);
+
+        // Where the next ring is split, each cell has two cells outward of it,
+        // otherwise just the one.
+        assert_eq!(
+            polar_grid.outward(IVec2::new(5, 2)),
+            [IVec2::new(10, 3), IVec2::new(11, 3)]
+        );
+        assert_eq!(polar_grid.inward(IVec2::new(11, 3)), Some(IVec2::new(5, 2)));
+        assert_eq!(polar_grid.outward(IVec2::new(5, 3)), [IVec2::new(5, 4)]);
+        assert_eq!(polar_grid.outward(IVec2::new(5, 7)), []);
+    }
+
+    #[test]
+    fn the_centre_cell_borders_every_cell_of_the_first_ring() {
+        let polar_grid = PolarGrid::new(8);
+        let centre = IVec2::ZERO;
+        assert_eq!(polar_grid.inward(centre), None);
+        assert_eq!(polar_grid.clockwise(centre), None);
+        assert_eq!(polar_grid.counter_clockwise(centre), None);
+
+        let first_ring: Vec<IVec2> = (0..6).map(|x| IVec2::new(x, 1)).collect();
+        assert_eq!(polar_grid.neighbours(centre), first_ring);
+        for cell in first_ring {
+            assert_eq!(polar_grid.inward(cell), Some(centre));
+        }
+    }
+
+    #[test]
+    fn rings_wrap_around_at_their_first_cell() {
+        let polar_grid = PolarGrid::new(8);
+        assert_eq!(
+            polar_grid.clockwise(IVec2::new(0, 3)),
+            Some(IVec2::new(23, 3))
+        );
+        assert_eq!(
+            polar_grid.counter_clockwise(IVec2::new(23, 3)),
+            Some(IVec2::new(0, 3))
+        );
+        assert_eq!(
+            polar_grid.wall_between(IVec2::new(23, 3), IVec2::new(0, 3)),
+            Some(Wall {
+                from: IVec2::new(0, 3),
+                to: IVec2::new(23, 3),
+            })
+        );
+    }
+
+    #[test]
+    fn neighbours_share_the_same_wall_from_either_side() {
+        let polar_grid = PolarGrid::new(8);
+        let mut wall_count = 0;
+        for cell in polar_grid.cells() {
+            for neighbour in polar_grid.neighbours(cell) {
+                assert!(polar_grid.is_cell_pos_in_bounds(neighbour), "{cell}");
+                assert!(polar_grid.neighbours(neighbour).contains(&cell), "{cell}");
+
+                let wall = polar_grid.wall_between(cell, neighbour);
+                assert!(wall.is_some(), "{cell}");
+                assert_eq!(wall, polar_grid.wall_between(neighbour, cell), "{cell}");
+                wall_count += 1;
+            }
+        }
+
+        // Each wall was found once from each side.
+        assert_eq!(wall_count, polar_grid.get_walls().len() * 2);
+        assert_eq!(polar_grid.wall_between(IVec2::ZERO, IVec2::new(0, 2)), None);
+    }
+}
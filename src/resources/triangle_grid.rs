@@ -0,0 +1,318 @@
+use super::{
+    grid_map::index_to_pos, grid_map::pos_to_index, DrawableGrid, Grid, LazyWallIndex, WallSet,
+};
+use crate::components::Wall;
+use bevy::prelude::*;
+use fastrand::Rng;
+use std::fmt;
+
+// The height of a triangle with sides one unit long.
+const TRIANGLE_HEIGHT: f32 = 0.866_025_4;
+
+const DIRECTIONS: [Dir2; 4] = [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TriangleGridError {
+    // A single column of cells is only joined up in pairs, so builders couldn't
+    // reach every cell.
+    SingleColumn { rows: i32 },
+}
+
+impl fmt::Display for TriangleGridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TriangleGridError::SingleColumn { rows } => write!(
+                f,
+                "a triangle grid with {rows} rows needs at least two columns, not one"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TriangleGridError {}
+
+// A grid of triangles laid out in columns and rows, where each cell alternates
+// between pointing up and pointing down. Cells only have three neighbours, the
+// cells either side in the same row, and the cell below if the cell points up,
+// or the cell above if it points down.
+//
+// Each cell is half a unit further to the right than the one before it, so the
+// triangles fit together. Cells are indexed the same way as a `GridMap`, a row at
+// a time from the bottom left, and the bottom left cell points up.
+#[derive(Resource)]
+pub struct TriangleGrid {
+    columns: i32,
+    rows: i32,
+    wall_index: LazyWallIndex,
+}
+
+impl TriangleGrid {
+    // A grid with more than one row needs at least two columns.
+    pub fn new(columns: i32, rows: i32) -> Result<Self, TriangleGridError> {
+        if columns == 1 && rows > 1 {
+            return Err(TriangleGridError::SingleColumn { rows });
+        }
+        Ok(TriangleGrid {
+            columns,
+            rows,
+            wall_index: LazyWallIndex::default(),
+        })
+    }
+
+    pub fn get_columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> i32 {
+        self.rows
+    }
+
+    pub fn get_cell_count(&self) -> i32 {
+        self.columns * self.rows
+    }
+
+    pub fn is_upright(&self, cell_pos: IVec2) -> bool {
+        (cell_pos.x + cell_pos.y) % 2 == 0
+    }
+
+    pub fn is_cell_pos_in_bounds(&self, cell_pos: IVec2) -> bool {
+        cell_pos.x >= 0 && cell_pos.x < self.columns && cell_pos.y >= 0 && cell_pos.y < self.rows
+    }
+
+    pub fn cell_pos_to_index(&self, cell_pos: IVec2) -> Option<i32> {
+        pos_to_index(cell_pos, self.columns, self.rows)
+    }
+
+    pub fn index_to_cell_pos(&self, index: i32) -> Option<IVec2> {
+        if index < 0 {
+            return None;
+        }
+
+        index_to_pos(index, self.columns, self.rows)
+    }
+
+    // Returns `None` for the north of a cell that points up and the south of a
+    // cell that points down, as those are corners rather than sides.
+    pub fn neighbour_from_cell_pos(&self, cell_pos: IVec2, direction: Dir2) -> Option<IVec2> {
+        if !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        let is_upright = self.is_upright(cell_pos);
+        let offset = if direction == Dir2::EAST {
+            IVec2 { x: 1, y: 0 }
+        } else if direction == Dir2::WEST {
+            IVec2 { x: -1, y: 0 }
+        } else if direction == Dir2::NORTH && !is_upright {
+            IVec2 { x: 0, y: 1 }
+        } else if direction == Dir2::SOUTH && is_upright {
+            IVec2 { x: 0, y: -1 }
+        } else {
+            return None;
+        };
+
+        let neighbour = cell_pos + offset;
+        match self.is_cell_pos_in_bounds(neighbour) {
+            true => Some(neighbour),
+            false => None,
+        }
+    }
+
+    pub fn iter_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.get_cell_count()).filter_map(|index| self.index_to_cell_pos(index))
+    }
+
+    pub fn get_cell_center(&self, cell_pos: IVec2) -> Vec2 {
+        let outline = self.get_cell_outline(cell_pos);
+        (outline[0] + outline[1] + outline[2]) / 3.0
+    }
+
+    // The three corners of the cell, going counter-clockwise from the bottom left
+    // for a cell that points up, or from the bottom for a cell that points down.
+    pub fn get_cell_outline(&self, cell_pos: IVec2) -> Vec<Vec2> {
+        let left = cell_pos.x as f32 * 0.5;
+        let bottom = cell_pos.y as f32 * TRIANGLE_HEIGHT;
+        let top = bottom + TRIANGLE_HEIGHT;
+
+        if self.is_upright(cell_pos) {
+            vec![
+                Vec2::new(left, bottom),
+                Vec2::new(left + 1.0, bottom),
+                Vec2::new(left + 0.5, top),
+            ]
+        } else {
+            vec![
+                Vec2::new(left + 0.5, bottom),
+                Vec2::new(left + 1.0, top),
+                Vec2::new(left, top),
+            ]
+        }
+    }
+
+    // The two corners either side of one of the cell's sides. The east and west
+    // sides are the diagonal ones.
+    pub fn get_side_outline(&self, cell_pos: IVec2, direction: Dir2) -> Vec<Vec2> {
+        let outline = self.get_cell_outline(cell_pos);
+        let is_upright = self.is_upright(cell_pos);
+        let corners = if direction == Dir2::WEST {
+            [2, 0]
+        } else if direction == Dir2::EAST && is_upright {
+            [1, 2]
+        } else if direction == Dir2::EAST {
+            [0, 1]
+        } else if direction == Dir2::NORTH && !is_upright {
+            [1, 2]
+        } else if direction == Dir2::SOUTH && is_upright {
+            [0, 1]
+        } else {
+            return vec![];
+        };
+
+        corners.map(|corner| outline[corner]).to_vec()
+    }
+
+    // The size of the grid, the bottom left of the grid is at the origin.
+    fn get_size(&self) -> Vec2 {
+        Vec2 {
+            x: (self.columns - 1).max(0) as f32 * 0.5 + 1.0,
+            y: self.rows as f32 * TRIANGLE_HEIGHT,
+        }
+    }
+}
+
+impl Grid for TriangleGrid {
+    type Cell = IVec2;
+    type Link = Wall;
+    type LinkSet = WallSet;
+
+    fn cell_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec2> + '_> {
+        Box::new(self.iter_cells())
+    }
+
+    fn cell_index(&self, cell: IVec2) -> Option<usize> {
+        Some(self.cell_pos_to_index(cell)? as usize)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec2> {
+        if self.get_cell_count() <= 0 {
+            return None;
+        }
+
+        self.index_to_cell_pos(rng.i32(0..self.get_cell_count()))
+    }
+
+    fn neighbours(&self, cell: IVec2) -> Vec<IVec2> {
+        DIRECTIONS
+            .into_iter()
+            .filter_map(|direction| self.neighbour_from_cell_pos(cell, direction))
+            .collect()
+    }
+
+    // The wall is identified by the two cells either side of it, the one with the
+    // lower index comes first.
+    fn wall_between(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+        if !self.neighbours(from).contains(&to) {
+            return None;
+        }
+
+        match self.cell_pos_to_index(from)? < self.cell_pos_to_index(to)? {
+            true => Some(Wall { from, to }),
+            false => Some(Wall { from: to, to: from }),
+        }
+    }
+
+    fn new_link_set(&self) -> WallSet {
+        self.wall_index.new_wall_set(|| self.get_walls())
+    }
+}
+
+impl DrawableGrid for TriangleGrid {
+    fn get_cell_center(&self, cell: IVec2) -> Vec2 {
+        TriangleGrid::get_cell_center(self, cell)
+    }
+
+    fn get_cell_outline(&self, cell: IVec2) -> Vec<Vec2> {
+        TriangleGrid::get_cell_outline(self, cell)
+    }
+
+    fn get_wall_outline(&self, wall: Wall) -> Vec<Vec2> {
+        let direction = DIRECTIONS
+            .into_iter()
+            .find(|direction| self.neighbour_from_cell_pos(wall.from, *direction) == Some(wall.to));
+
+        match direction {
+            Some(direction) => self.get_side_outline(wall.from, direction),
+            None => vec![],
+        }
+    }
+
+    fn get_boundary_outlines(&self) -> Vec<Vec<Vec2>> {
+        self.iter_cells()
+            .flat_map(|cell_pos| {
+                DIRECTIONS
+                    .into_iter()
+                    .filter(move |direction| {
+                        self.neighbour_from_cell_pos(cell_pos, *direction).is_none()
+                    })
+                    .map(move |direction| self.get_side_outline(cell_pos, direction))
+                    .filter(|side_outline| !side_outline.is_empty())
+            })
+            .collect()
+    }
+
+    fn get_scale_from_available_space(&self, available_space: Vec2) -> f32 {
+        let size = self.get_size();
+        (available_space.x / size.x).min(available_space.y / size.y)
+    }
+
+    fn get_centered_grid_pos(&self, scale: f32) -> Vec2 {
+        -(self.get_size() * scale) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::dijkstra;
+
+    #[test]
+    fn two_columns_are_enough_for_every_cell_to_be_reachable() {
+        let triangle_grid = TriangleGrid::new(2, 4).unwrap();
+        assert_eq!(triangle_grid.get_columns(), 2);
+
+        // With every wall removed, every cell should be reachable from the first.
+        let mut removed_walls = triangle_grid.new_link_set();
+        triangle_grid.cells().for_each(|cell| {
+            removed_walls.extend(
+                triangle_grid
+                    .neighbour_walls(cell)
+                    .into_iter()
+                    .map(|(_, wall)| wall),
+            );
+        });
+        let distances = dijkstra(IVec2::ZERO, &triangle_grid, &removed_walls);
+        assert_eq!(distances.len(), triangle_grid.cell_count());
+    }
+
+    #[test]
+    fn empty_grids_and_a_single_cell_are_allowed() {
+        assert_eq!(TriangleGrid::new(0, 4).unwrap().cell_count(), 0);
+        assert_eq!(TriangleGrid::new(1, 1).unwrap().cell_count(), 1);
+    }
+
+    #[test]
+    fn single_column_is_rejected() {
+        assert_eq!(
+            TriangleGrid::new(1, 4).err(),
+            Some(TriangleGridError::SingleColumn { rows: 4 })
+        );
+    }
+}
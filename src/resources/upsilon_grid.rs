@@ -0,0 +1,357 @@
+use super::{
+    grid_map::index_to_pos, grid_map::pos_to_index, DrawableGrid, Grid, LazyWallIndex, WallSet,
+};
+use crate::components::Wall;
+use bevy::prelude::*;
+use fastrand::Rng;
+
+// The distance from the center of an octagon to the middle of each of its sides.
+// Octagons are one unit apart diagonally, so they meet along their diagonal sides.
+const OCTAGON_APOTHEM: f32 = std::f32::consts::FRAC_1_SQRT_2;
+// The distance from the center of an octagon to each of its corners.
+const OCTAGON_RADIUS: f32 = 0.765_366_85;
+// Half the length of a square's side, which fills the gap between four octagons.
+const SQUARE_HALF_SIDE: f32 = 1.0 - OCTAGON_APOTHEM;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum UpsilonDirection {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl UpsilonDirection {
+    // Going clockwise from the north.
+    pub const ALL: [UpsilonDirection; 8] = [
+        UpsilonDirection::North,
+        UpsilonDirection::NorthEast,
+        UpsilonDirection::East,
+        UpsilonDirection::SouthEast,
+        UpsilonDirection::South,
+        UpsilonDirection::SouthWest,
+        UpsilonDirection::West,
+        UpsilonDirection::NorthWest,
+    ];
+
+    pub fn is_diagonal(&self) -> bool {
+        matches!(
+            self,
+            UpsilonDirection::NorthEast
+                | UpsilonDirection::SouthEast
+                | UpsilonDirection::SouthWest
+                | UpsilonDirection::NorthWest
+        )
+    }
+
+    fn get_offset(&self) -> IVec2 {
+        match self {
+            UpsilonDirection::North => IVec2 { x: 0, y: 1 },
+            UpsilonDirection::NorthEast => IVec2 { x: 1, y: 1 },
+            UpsilonDirection::East => IVec2 { x: 1, y: 0 },
+            UpsilonDirection::SouthEast => IVec2 { x: 1, y: -1 },
+            UpsilonDirection::South => IVec2 { x: 0, y: -1 },
+            UpsilonDirection::SouthWest => IVec2 { x: -1, y: -1 },
+            UpsilonDirection::West => IVec2 { x: -1, y: 0 },
+            UpsilonDirection::NorthWest => IVec2 { x: -1, y: 1 },
+        }
+    }
+
+    // The two corners of a cell that this side is between, see `get_cell_outline`.
+    fn get_corners(&self, is_octagon: bool) -> (usize, usize) {
+        match (self, is_octagon) {
+            (UpsilonDirection::East, true) => (7, 0),
+            (UpsilonDirection::NorthEast, true) => (0, 1),
+            (UpsilonDirection::North, true) => (1, 2),
+            (UpsilonDirection::NorthWest, true) => (2, 3),
+            (UpsilonDirection::West, true) => (3, 4),
+            (UpsilonDirection::SouthWest, true) => (4, 5),
+            (UpsilonDirection::South, true) => (5, 6),
+            (UpsilonDirection::SouthEast, true) => (6, 7),
+            (UpsilonDirection::East, false) => (0, 1),
+            (UpsilonDirection::North, false) => (1, 2),
+            (UpsilonDirection::West, false) => (2, 3),
+            (UpsilonDirection::South, false) => (3, 0),
+            // Squares don't have diagonal sides.
+            (_, false) => (0, 0),
+        }
+    }
+}
+
+// A grid of octagons with squares in the gaps between them, laid out in columns
+// and rows like a `GridMap`. Cells where the column and row add up to an even
+// number are octagons, which neighbour the eight cells around them, including the
+// octagons on the diagonals. The others are squares, which only neighbour the
+// four octagons next to them.
+//
+// Cells are indexed the same way as a `GridMap`, a row at a time from the
+// bottom left.
+#[derive(Resource)]
+pub struct UpsilonGrid {
+    columns: i32,
+    rows: i32,
+    wall_index: LazyWallIndex,
+}
+
+impl UpsilonGrid {
+    pub fn new(columns: i32, rows: i32) -> Self {
+        UpsilonGrid {
+            columns,
+            rows,
+            wall_index: LazyWallIndex::default(),
+        }
+    }
+
+    pub fn get_columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> i32 {
+        self.rows
+    }
+
+    pub fn get_cell_count(&self) -> i32 {
+        self.columns * self.rows
+    }
+
+    pub fn is_cell_pos_in_bounds(&self, cell_pos: IVec2) -> bool {
+        cell_pos.x >= 0 && cell_pos.x < self.columns && cell_pos.y >= 0 && cell_pos.y < self.rows
+    }
+
+    pub fn is_octagon(&self, cell_pos: IVec2) -> bool {
+        (cell_pos.x + cell_pos.y) % 2 == 0
+    }
+
+    pub fn cell_pos_to_index(&self, cell_pos: IVec2) -> Option<i32> {
+        pos_to_index(cell_pos, self.columns, self.rows)
+    }
+
+    pub fn index_to_cell_pos(&self, index: i32) -> Option<IVec2> {
+        if index < 0 {
+            return None;
+        }
+
+        index_to_pos(index, self.columns, self.rows)
+    }
+
+    pub fn neighbour_from_cell_pos(
+        &self,
+        cell_pos: IVec2,
+        direction: UpsilonDirection,
+    ) -> Option<IVec2> {
+        if !self.is_cell_pos_in_bounds(cell_pos) {
+            return None;
+        }
+
+        if direction.is_diagonal() && !self.is_octagon(cell_pos) {
+            return None;
+        }
+
+        let neighbour = cell_pos + direction.get_offset();
+        match self.is_cell_pos_in_bounds(neighbour) {
+            true => Some(neighbour),
+            false => None,
+        }
+    }
+
+    pub fn iter_cells(&self) -> impl Iterator<Item = IVec2> + '_ {
+        (0..self.get_cell_count()).filter_map(|index| self.index_to_cell_pos(index))
+    }
+
+    pub fn get_cell_center(&self, cell_pos: IVec2) -> Vec2 {
+        cell_pos.as_vec2()
+    }
+
+    // The corners of the cell going counter-clockwise, from just north of east for
+    // an octagon, and from the south east corner for a square.
+    pub fn get_cell_outline(&self, cell_pos: IVec2) -> Vec<Vec2> {
+        let center = self.get_cell_center(cell_pos);
+        if !self.is_octagon(cell_pos) {
+            return [
+                Vec2::new(1.0, -1.0),
+                Vec2::new(1.0, 1.0),
+                Vec2::new(-1.0, 1.0),
+                Vec2::new(-1.0, -1.0),
+            ]
+            .into_iter()
+            .map(|corner| center + corner * SQUARE_HALF_SIDE)
+            .collect();
+        }
+
+        (0..8)
+            .map(|corner| {
+                let angle = (22.5 + corner as f32 * 45.0).to_radians();
+                center + Vec2::from_angle(angle) * OCTAGON_RADIUS
+            })
+            .collect()
+    }
+
+    pub fn get_side_outline(&self, cell_pos: IVec2, direction: UpsilonDirection) -> Vec<Vec2> {
+        let outline = self.get_cell_outline(cell_pos);
+        let (start_corner, end_corner) = direction.get_corners(self.is_octagon(cell_pos));
+        vec![outline[start_corner], outline[end_corner]]
+    }
+
+    // The size of the grid, and the position of its bottom left corner. Octagons
+    // stick out further than squares, so there's room for them on every side.
+    fn get_bounds(&self) -> (Vec2, Vec2) {
+        let size = Vec2 {
+            x: (self.columns - 1).max(0) as f32 + OCTAGON_APOTHEM * 2.0,
+            y: (self.rows - 1).max(0) as f32 + OCTAGON_APOTHEM * 2.0,
+        };
+        (size, Vec2::splat(-OCTAGON_APOTHEM))
+    }
+}
+
+impl Grid for UpsilonGrid {
+    type Cell = IVec2;
+    type Link = Wall;
+    type LinkSet = WallSet;
+
+    fn cell_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec2> + '_> {
+        Box::new(self.iter_cells())
+    }
+
+    fn cell_index(&self, cell: IVec2) -> Option<usize> {
+        Some(self.cell_pos_to_index(cell)? as usize)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.get_cell_count().max(0) as usize
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec2> {
+        if self.get_cell_count() <= 0 {
+            return None;
+        }
+
+        self.index_to_cell_pos(rng.i32(0..self.get_cell_count()))
+    }
+
+    fn neighbours(&self, cell: IVec2) -> Vec<IVec2> {
+        UpsilonDirection::ALL
+            .into_iter()
+            .filter_map(|direction| self.neighbour_from_cell_pos(cell, direction))
+            .collect()
+    }
+
+    // The wall is identified by the two cells either side of it, the one with the
+    // lower index comes first.
+    fn wall_between(&self, from: IVec2, to: IVec2) -> Option<Wall> {
+        if !self.neighbours(from).contains(&to) {
+            return None;
+        }
+
+        match self.cell_pos_to_index(from)? < self.cell_pos_to_index(to)? {
+            true => Some(Wall { from, to }),
+            false => Some(Wall { from: to, to: from }),
+        }
+    }
+
+    fn new_link_set(&self) -> WallSet {
+        self.wall_index.new_wall_set(|| self.get_walls())
+    }
+}
+
+impl DrawableGrid for UpsilonGrid {
+    fn get_cell_center(&self, cell: IVec2) -> Vec2 {
+        UpsilonGrid::get_cell_center(self, cell)
+    }
+
+    fn get_cell_outline(&self, cell: IVec2) -> Vec<Vec2> {
+        UpsilonGrid::get_cell_outline(self, cell)
+    }
+
+    fn get_wall_outline(&self, wall: Wall) -> Vec<Vec2> {
+        let direction = UpsilonDirection::ALL
+            .into_iter()
+            .find(|direction| self.neighbour_from_cell_pos(wall.from, *direction) == Some(wall.to));
+
+        match direction {
+            Some(direction) => self.get_side_outline(wall.from, direction),
+            None => vec![],
+        }
+    }
+
+    fn get_boundary_outlines(&self) -> Vec<Vec<Vec2>> {
+        self.iter_cells()
+            .flat_map(|cell_pos| {
+                UpsilonDirection::ALL
+                    .into_iter()
+                    .filter(move |direction| !direction.is_diagonal() || self.is_octagon(cell_pos))
+                    .filter(move |direction| {
+                        self.neighbour_from_cell_pos(cell_pos, *direction).is_none()
+                    })
+                    .map(move |direction| self.get_side_outline(cell_pos, direction))
+            })
+            .collect()
+    }
+
+    fn get_scale_from_available_space(&self, available_space: Vec2) -> f32 {
+        let (size, _) = self.get_bounds();
+        (available_space.x / size.x).min(available_space.y / size.y)
+    }
+
+    fn get_centered_grid_pos(&self, scale: f32) -> Vec2 {
+        let (size, bottom_left) = self.get_bounds();
+        -(bottom_left + size / 2.0) * scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octagons_and_squares_are_neighbours_of_each_other() {
+        let upsilon_grid = UpsilonGrid::new(5, 5);
+        let octagon = IVec2::new(2, 2);
+        let square = IVec2::new(1, 2);
+        assert!(upsilon_grid.is_octagon(octagon));
+        assert!(!upsilon_grid.is_octagon(square));
+
+        assert_eq!(upsilon_grid.neighbours(octagon).len(), 8);
+        assert_eq!(upsilon_grid.neighbours(square).len(), 4);
+        assert!(upsilon_grid
+            .neighbours(square)
+            .iter()
+            .all(|neighbour| upsilon_grid.is_octagon(*neighbour)));
+
+        // Octagons only meet the octagons on their diagonals, and the corners only
+        // have the neighbours that are in the grid.
+        assert_eq!(
+            upsilon_grid.neighbour_from_cell_pos(octagon, UpsilonDirection::NorthEast),
+            Some(IVec2::new(3, 3))
+        );
+        assert_eq!(
+            upsilon_grid.neighbour_from_cell_pos(square, UpsilonDirection::NorthEast),
+            None
+        );
+        assert_eq!(upsilon_grid.neighbours(IVec2::ZERO).len(), 3);
+    }
+
+    #[test]
+    fn neighbours_share_the_same_wall_from_either_side() {
+        let upsilon_grid = UpsilonGrid::new(6, 5);
+        for cell in upsilon_grid.cells() {
+            for neighbour in upsilon_grid.neighbours(cell) {
+                assert!(upsilon_grid.neighbours(neighbour).contains(&cell), "{cell}");
+                let wall = upsilon_grid.wall_between(cell, neighbour);
+                assert!(wall.is_some(), "{cell}");
+                assert_eq!(wall, upsilon_grid.wall_between(neighbour, cell), "{cell}");
+            }
+        }
+        assert_eq!(
+            upsilon_grid.wall_between(IVec2::new(1, 2), IVec2::new(2, 3)),
+            None
+        );
+    }
+}
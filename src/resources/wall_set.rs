@@ -0,0 +1,602 @@
+use super::grid_map::{index_to_wall_pos, wall_pos_to_index};
+use crate::components::{Wall, WallOrientation};
+use bevy::{prelude::*, utils::HashMap};
+use std::collections::{btree_set, BTreeSet};
+use std::sync::{Arc, OnceLock};
+
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+// A set of walls, stored as one bit for each wall of a grid map of a given size,
+// with a bitset for the horizontal walls and another for the vertical walls.
+//
+// The other shapes of grid number their walls with a `WallIndex` instead, and
+// sets made with `with_index` keep a bit for each of those walls.
+//
+// Walls that have neither, like walls outside the grid map, are kept separately,
+// so any wall can still be stored. Iterating goes through the horizontal walls
+// then the vertical walls in index order, then the indexed walls, then those
+// other walls in order of their positions, so the order is always the same for
+// the same walls.
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct WallSet {
+    columns: i32,
+    rows: i32,
+    horizontal: Vec<u64>,
+    vertical: Vec<u64>,
+    wall_index: Option<Arc<WallIndex>>,
+    indexed: Vec<u64>,
+    other_walls: BTreeSet<[i32; 4]>,
+    len: usize,
+}
+
+// A number for each wall of a grid, for grids whose walls aren't between
+// neighbouring points of a grid map. Grids make one and share it between every
+// `WallSet` they make, see `with_index`.
+#[derive(PartialEq, Eq)]
+pub struct WallIndex {
+    walls: Vec<Wall>,
+    indexes: HashMap<Wall, usize>,
+}
+
+impl WallIndex {
+    // Each wall is numbered in the order it's given in, repeated walls are skipped.
+    pub fn new(walls: impl IntoIterator<Item = Wall>) -> Self {
+        let mut wall_index = WallIndex {
+            walls: vec![],
+            indexes: HashMap::new(),
+        };
+        walls.into_iter().for_each(|wall| {
+            let next_index = wall_index.walls.len();
+            wall_index.indexes.entry(wall).or_insert_with(|| {
+                wall_index.walls.push(wall);
+                next_index
+            });
+        });
+        wall_index
+    }
+
+    pub fn len(&self) -> usize {
+        self.walls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.walls.is_empty()
+    }
+
+    pub fn get_index(&self, wall: &Wall) -> Option<usize> {
+        self.indexes.get(wall).copied()
+    }
+
+    pub fn get_wall(&self, index: usize) -> Option<Wall> {
+        self.walls.get(index).copied()
+    }
+}
+
+// The `WallIndex` a grid shares between the sets it makes. Numbering the walls
+// can take a while on large grids, so it's left until the first set is made.
+#[derive(Default)]
+pub struct LazyWallIndex(OnceLock<Arc<WallIndex>>);
+
+impl LazyWallIndex {
+    // An empty set for the grid's walls, `get_walls` is only called the first time.
+    pub fn new_wall_set(&self, get_walls: impl FnOnce() -> Vec<Wall>) -> WallSet {
+        let wall_index = self.0.get_or_init(|| Arc::new(WallIndex::new(get_walls())));
+        WallSet::with_index(wall_index.clone())
+    }
+}
+
+// Which of a `WallSet`'s bitsets a wall is kept in.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Bits {
+    Horizontal,
+    Vertical,
+    Indexed,
+}
+
+impl WallSet {
+    pub fn new(columns: i32, rows: i32) -> Self {
+        let columns = columns.max(0);
+        let rows = rows.max(0);
+        WallSet {
+            columns,
+            rows,
+            horizontal: vec![0; get_word_count(columns * (rows + 1))],
+            vertical: vec![0; get_word_count((columns + 1) * rows)],
+            wall_index: None,
+            indexed: vec![],
+            other_walls: BTreeSet::new(),
+            len: 0,
+        }
+    }
+
+    // An empty set with a bit for each of the walls in `wall_index`.
+    pub fn with_index(wall_index: Arc<WallIndex>) -> Self {
+        WallSet {
+            indexed: vec![0; wall_index.len().div_ceil(BITS_PER_WORD)],
+            wall_index: Some(wall_index),
+            ..WallSet::default()
+        }
+    }
+
+    // An empty set that keeps the same walls as bits as this one.
+    fn new_like(&self) -> Self {
+        let mut wall_set = WallSet::new(self.columns, self.rows);
+        if let Some(wall_index) = &self.wall_index {
+            wall_set.indexed = vec![0; self.indexed.len()];
+            wall_set.wall_index = Some(wall_index.clone());
+        }
+        wall_set
+    }
+
+    pub fn get_columns(&self) -> i32 {
+        self.columns
+    }
+
+    pub fn get_rows(&self) -> i32 {
+        self.rows
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Returns whether the wall wasn't already in the set.
+    pub fn insert(&mut self, wall: Wall) -> bool {
+        let is_inserted = match self.get_bit(wall) {
+            Some((bits, word, mask)) => {
+                let bits = self.get_bits_mut(bits);
+                let is_inserted = bits[word] & mask == 0;
+                bits[word] |= mask;
+                is_inserted
+            }
+            None => self.other_walls.insert(wall_to_key(wall)),
+        };
+
+        if is_inserted {
+            self.len += 1;
+        }
+        is_inserted
+    }
+
+    // Returns whether the wall was in the set.
+    pub fn remove(&mut self, wall: &Wall) -> bool {
+        let is_removed = match self.get_bit(*wall) {
+            Some((bits, word, mask)) => {
+                let bits = self.get_bits_mut(bits);
+                let is_removed = bits[word] & mask != 0;
+                bits[word] &= !mask;
+                is_removed
+            }
+            None => self.other_walls.remove(&wall_to_key(*wall)),
+        };
+
+        if is_removed {
+            self.len -= 1;
+        }
+        is_removed
+    }
+
+    pub fn contains(&self, wall: &Wall) -> bool {
+        match self.get_bit(*wall) {
+            Some((bits, word, mask)) => self.get_bits(bits)[word] & mask != 0,
+            None => self.other_walls.contains(&wall_to_key(*wall)),
+        }
+    }
+
+    // Whether the grid map wall at `wall_pos_to_index`'s index is in the set,
+    // without making the wall to look it up.
+    pub(super) fn contains_index(&self, orientation: WallOrientation, index: usize) -> bool {
+        let bits = match orientation {
+            WallOrientation::Horizontal => Bits::Horizontal,
+            WallOrientation::Vertical => Bits::Vertical,
+        };
+        self.get_bits(bits)
+            .get(index / BITS_PER_WORD)
+            .is_some_and(|word| word & (1 << (index % BITS_PER_WORD)) != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.horizontal.fill(0);
+        self.vertical.fill(0);
+        self.indexed.fill(0);
+        self.other_walls.clear();
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> WallSetIterator<'_> {
+        WallSetIterator {
+            wall_set: self,
+            bits: Bits::Horizontal,
+            next_word_index: 0,
+            word: 0,
+            other_walls: self.other_walls.iter(),
+        }
+    }
+
+    // The walls in either set.
+    pub fn union(&self, other: &WallSet) -> WallSet {
+        self.combine(other, |a, b| a | b, |a, b| a || b)
+    }
+
+    // The walls in both sets.
+    pub fn intersection(&self, other: &WallSet) -> WallSet {
+        self.combine(other, |a, b| a & b, |a, b| a && b)
+    }
+
+    // The walls in this set that aren't in `other`.
+    pub fn difference(&self, other: &WallSet) -> WallSet {
+        self.combine(other, |a, b| a & !b, |a, b| a && !b)
+    }
+
+    pub fn is_subset(&self, other: &WallSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    // Combines the sets a word of bits at a time, the result keeps the same walls
+    // as bits as this set. Sets of different sizes, or with different wall indexes,
+    // have their walls at different indexes, so those are combined a wall at a
+    // time instead.
+    fn combine(
+        &self,
+        other: &WallSet,
+        combine_words: fn(u64, u64) -> u64,
+        combine_contains: fn(bool, bool) -> bool,
+    ) -> WallSet {
+        let has_same_index = match (&self.wall_index, &other.wall_index) {
+            (Some(wall_index), Some(other_wall_index)) => Arc::ptr_eq(wall_index, other_wall_index),
+            (None, None) => true,
+            _ => false,
+        };
+        if self.columns != other.columns || self.rows != other.rows || !has_same_index {
+            let mut combined = self.new_like();
+            combined.extend(
+                self.iter()
+                    .chain(other.iter())
+                    .filter(|wall| combine_contains(self.contains(wall), other.contains(wall))),
+            );
+            return combined;
+        }
+
+        let combine_bits = |bits: &[u64], other_bits: &[u64]| -> Vec<u64> {
+            bits.iter()
+                .zip(other_bits)
+                .map(|(word, other_word)| combine_words(*word, *other_word))
+                .collect()
+        };
+        let horizontal = combine_bits(&self.horizontal, &other.horizontal);
+        let vertical = combine_bits(&self.vertical, &other.vertical);
+        let indexed = combine_bits(&self.indexed, &other.indexed);
+        let other_walls: BTreeSet<[i32; 4]> = self
+            .other_walls
+            .union(&other.other_walls)
+            .filter(|key| {
+                combine_contains(
+                    self.other_walls.contains(*key),
+                    other.other_walls.contains(*key),
+                )
+            })
+            .copied()
+            .collect();
+
+        let len = horizontal
+            .iter()
+            .chain(&vertical)
+            .chain(&indexed)
+            .map(|word| word.count_ones() as usize)
+            .sum::<usize>()
+            + other_walls.len();
+
+        WallSet {
+            columns: self.columns,
+            rows: self.rows,
+            horizontal,
+            vertical,
+            wall_index: self.wall_index.clone(),
+            indexed,
+            other_walls,
+            len,
+        }
+    }
+
+    // The bitset a wall's bit is in, the word it's in and the mask for it, or
+    // `None` if the wall is neither one of the grid map's nor an indexed wall.
+    fn get_bit(&self, wall: Wall) -> Option<(Bits, usize, u64)> {
+        let (bits, index) = match wall_pos_to_index(wall, self.columns, self.rows) {
+            Some((WallOrientation::Horizontal, index)) => (Bits::Horizontal, index as usize),
+            Some((WallOrientation::Vertical, index)) => (Bits::Vertical, index as usize),
+            None => (Bits::Indexed, self.wall_index.as_ref()?.get_index(&wall)?),
+        };
+        Some((bits, index / BITS_PER_WORD, 1 << (index % BITS_PER_WORD)))
+    }
+
+    fn get_bits(&self, bits: Bits) -> &[u64] {
+        match bits {
+            Bits::Horizontal => &self.horizontal,
+            Bits::Vertical => &self.vertical,
+            Bits::Indexed => &self.indexed,
+        }
+    }
+
+    fn get_bits_mut(&mut self, bits: Bits) -> &mut [u64] {
+        match bits {
+            Bits::Horizontal => &mut self.horizontal,
+            Bits::Vertical => &mut self.vertical,
+            Bits::Indexed => &mut self.indexed,
+        }
+    }
+}
+
+impl Extend<Wall> for WallSet {
+    fn extend<T: IntoIterator<Item = Wall>>(&mut self, walls: T) {
+        walls.into_iter().for_each(|wall| {
+            self.insert(wall);
+        });
+    }
+}
+
+impl<'a> IntoIterator for &'a WallSet {
+    type Item = Wall;
+    type IntoIter = WallSetIterator<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct WallSetIterator<'a> {
+    wall_set: &'a WallSet,
+    bits: Bits,
+    next_word_index: usize,
+    // The bits of the current word that haven't been returned yet.
+    word: u64,
+    other_walls: btree_set::Iter<'a, [i32; 4]>,
+}
+
+impl<'a> Iterator for WallSetIterator<'a> {
+    type Item = Wall;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.word != 0 {
+                let bit = self.word.trailing_zeros() as usize;
+                self.word &= self.word - 1;
+                let index = (self.next_word_index - 1) * BITS_PER_WORD + bit;
+                let orientation = match self.bits {
+                    Bits::Horizontal => WallOrientation::Horizontal,
+                    Bits::Vertical => WallOrientation::Vertical,
+                    Bits::Indexed => {
+                        return self.wall_set.wall_index.as_ref()?.get_wall(index);
+                    }
+                };
+                return index_to_wall_pos(
+                    index as i32,
+                    orientation,
+                    self.wall_set.columns,
+                    self.wall_set.rows,
+                );
+            }
+
+            let bits = self.wall_set.get_bits(self.bits);
+            if self.next_word_index < bits.len() {
+                self.word = bits[self.next_word_index];
+                self.next_word_index += 1;
+                continue;
+            }
+
+            match self.bits {
+                Bits::Horizontal => self.bits = Bits::Vertical,
+                Bits::Vertical => self.bits = Bits::Indexed,
+                Bits::Indexed => return self.other_walls.next().map(|key| key_to_wall(*key)),
+            }
+            self.next_word_index = 0;
+        }
+    }
+}
+
+fn get_word_count(bit_count: i32) -> usize {
+    (bit_count.max(0) as usize).div_ceil(BITS_PER_WORD)
+}
+
+fn wall_to_key(wall: Wall) -> [i32; 4] {
+    [wall.from.x, wall.from.y, wall.to.x, wall.to.y]
+}
+
+fn key_to_wall(key: [i32; 4]) -> Wall {
+    Wall {
+        from: IVec2::new(key[0], key[1]),
+        to: IVec2::new(key[2], key[3]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wall(from: (i32, i32), to: (i32, i32)) -> Wall {
+        Wall {
+            from: IVec2::new(from.0, from.1),
+            to: IVec2::new(to.0, to.1),
+        }
+    }
+
+    #[test]
+    fn insert_contains_and_remove() {
+        let mut wall_set = WallSet::new(3, 2);
+        let horizontal = wall((1, 2), (2, 2));
+        let vertical = wall((3, 0), (3, 1));
+
+        assert!(wall_set.is_empty());
+        assert!(wall_set.insert(horizontal));
+        assert!(wall_set.insert(vertical));
+        assert!(!wall_set.insert(horizontal));
+        assert_eq!(wall_set.len(), 2);
+        assert!(wall_set.contains(&horizontal));
+        assert!(wall_set.contains(&vertical));
+        assert!(!wall_set.contains(&wall((0, 0), (1, 0))));
+
+        assert!(wall_set.remove(&horizontal));
+        assert!(!wall_set.remove(&horizontal));
+        assert_eq!(wall_set.len(), 1);
+        assert!(!wall_set.contains(&horizontal));
+
+        wall_set.clear();
+        assert!(wall_set.is_empty());
+        assert!(!wall_set.contains(&vertical));
+    }
+
+    #[test]
+    fn walls_outside_the_bitsets_are_kept_separately() {
+        let mut wall_set = WallSet::new(2, 2);
+        // Outside the grid map, and not between neighbouring points at all.
+        let outside = wall((5, 5), (6, 5));
+        let diagonal = wall((0, 0), (1, 1));
+
+        assert!(wall_set.insert(outside));
+        assert!(wall_set.insert(diagonal));
+        assert!(!wall_set.insert(diagonal));
+        assert_eq!(wall_set.len(), 2);
+        assert!(wall_set.contains(&outside));
+        assert!(wall_set.contains(&diagonal));
+
+        assert!(wall_set.remove(&outside));
+        assert!(!wall_set.contains(&outside));
+        assert_eq!(wall_set.len(), 1);
+    }
+
+    #[test]
+    fn iterates_horizontal_then_vertical_then_other_walls() {
+        let walls = [
+            wall((9, 9), (9, 10)),
+            wall((2, 0), (2, 1)),
+            wall((0, 1), (1, 1)),
+            wall((1, 0), (2, 0)),
+        ];
+        let mut wall_set = WallSet::new(2, 1);
+        wall_set.extend(walls);
+
+        // Horizontal walls in index order, then vertical ones, then the rest.
+        let expected = vec![walls[3], walls[2], walls[1], walls[0]];
+        assert!(wall_set.iter().collect::<Vec<_>>() == expected);
+    }
+
+    #[test]
+    fn set_operations_on_the_same_size() {
+        let a_walls = [
+            wall((0, 0), (1, 0)),
+            wall((1, 0), (1, 1)),
+            wall((7, 7), (8, 8)),
+        ];
+        let b_walls = [
+            wall((1, 0), (1, 1)),
+            wall((0, 2), (1, 2)),
+            wall((7, 7), (8, 8)),
+        ];
+        let mut a = WallSet::new(2, 2);
+        a.extend(a_walls);
+        let mut b = WallSet::new(2, 2);
+        b.extend(b_walls);
+
+        let union = a.union(&b);
+        assert_eq!(union.len(), 4);
+        assert!(a_walls
+            .iter()
+            .chain(&b_walls)
+            .all(|wall| union.contains(wall)));
+
+        let intersection = a.intersection(&b);
+        assert_eq!(intersection.len(), 2);
+        assert!(intersection.contains(&a_walls[1]));
+        assert!(intersection.contains(&a_walls[2]));
+
+        let difference = a.difference(&b);
+        assert!(difference.iter().collect::<Vec<_>>() == vec![a_walls[0]]);
+
+        assert!(intersection.is_subset(&a));
+        assert!(intersection.is_subset(&b));
+        assert!(!a.is_subset(&b));
+        assert!(a.is_subset(&union));
+    }
+
+    #[test]
+    fn set_operations_on_different_sizes() {
+        // Walls that are in the bitsets of the larger set are other walls of the
+        // smaller one, so they're combined a wall at a time.
+        let shared = wall((0, 0), (1, 0));
+        let only_in_large = wall((4, 3), (5, 3));
+        let mut small = WallSet::new(2, 2);
+        small.extend([shared]);
+        let mut large = WallSet::new(6, 4);
+        large.extend([shared, only_in_large]);
+
+        let union = small.union(&large);
+        assert_eq!((union.get_columns(), union.get_rows()), (2, 2));
+        assert_eq!(union.len(), 2);
+        assert!(union.contains(&only_in_large));
+
+        let intersection = large.intersection(&small);
+        assert!(intersection.iter().collect::<Vec<_>>() == vec![shared]);
+
+        let difference = large.difference(&small);
+        assert!(difference.iter().collect::<Vec<_>>() == vec![only_in_large]);
+
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+    }
+
+    #[test]
+    fn indexed_walls_are_kept_in_index_order() {
+        // Walls between cells, as the grids that aren't grid maps have.
+        let indexed_walls = [
+            wall((0, 0), (1, 1)),
+            wall((2, 0), (0, 3)),
+            wall((1, 1), (5, 2)),
+        ];
+        let wall_index = Arc::new(WallIndex::new(indexed_walls));
+        assert_eq!(wall_index.len(), 3);
+        assert_eq!(wall_index.get_index(&indexed_walls[1]), Some(1));
+
+        let not_indexed = wall((9, 9), (9, 8));
+        let mut wall_set = WallSet::with_index(wall_index.clone());
+        wall_set.extend([not_indexed, indexed_walls[2], indexed_walls[0]]);
+        assert_eq!(wall_set.len(), 3);
+        assert!(wall_set.contains(&indexed_walls[0]));
+        assert!(!wall_set.contains(&indexed_walls[1]));
+        assert!(
+            wall_set.iter().collect::<Vec<_>>()
+                == vec![indexed_walls[0], indexed_walls[2], not_indexed]
+        );
+
+        let mut other = WallSet::with_index(wall_index);
+        other.extend([indexed_walls[1], indexed_walls[2]]);
+        let union = wall_set.union(&other);
+        assert_eq!(union.len(), 4);
+        assert!(union.iter().take(3).collect::<Vec<_>>() == indexed_walls);
+        assert!(wall_set.intersection(&other).iter().collect::<Vec<_>>() == vec![indexed_walls[2]]);
+
+        // A set with its own index still combines with it, a wall at a time.
+        let mut separately_indexed = WallSet::with_index(Arc::new(WallIndex::new(indexed_walls)));
+        separately_indexed.extend([indexed_walls[0]]);
+        assert!(separately_indexed.is_subset(&wall_set));
+        assert!(!wall_set.is_subset(&separately_indexed));
+    }
+
+    #[test]
+    fn lazy_wall_indexes_number_the_walls_once() {
+        let walls = vec![wall((0, 0), (1, 1)), wall((1, 1), (2, 0))];
+        let lazy_wall_index = LazyWallIndex::default();
+        let mut wall_set = lazy_wall_index.new_wall_set(|| walls.clone());
+        let other = lazy_wall_index.new_wall_set(|| unreachable!());
+
+        wall_set.insert(walls[1]);
+        assert!(wall_set.union(&other).iter().collect::<Vec<_>>() == vec![walls[1]]);
+        assert!(wall_set.wall_index.is_some());
+        assert!(Arc::ptr_eq(
+            wall_set.wall_index.as_ref().unwrap(),
+            other.wall_index.as_ref().unwrap()
+        ));
+    }
+}
@@ -0,0 +1,434 @@
+use super::{Grid, GridMap, WallSet};
+use crate::components::Wall;
+use bevy::{prelude::*, utils::HashSet};
+use fastrand::Rng;
+
+const DIRECTIONS: [Dir2; 4] = [Dir2::NORTH, Dir2::EAST, Dir2::SOUTH, Dir2::WEST];
+
+// The link between two neighbouring cells of a `WeaveGrid`.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum WeaveLink {
+    // A passage between two cells on top.
+    Wall(Wall),
+    // A passage through a wall between a cell on top and the tunnel under the
+    // cell on the other side, `under` is the cell the tunnel goes under.
+    Tunnel { wall: Wall, under: IVec2 },
+}
+
+// A square grid where a passage can tunnel under a cell, as long as the cell is a
+// straight corridor going across the tunnel. Cells are an `IVec3`, where z is 0
+// for the cell on top, and 1 for the tunnel under it.
+//
+// A tunnel only goes under one cell, from the cell on top on one side of it to the
+// cell on top on the other side.
+#[derive(Resource)]
+pub struct WeaveGrid {
+    grid_map: GridMap,
+}
+
+impl WeaveGrid {
+    pub fn new(columns: i32, rows: i32) -> Self {
+        WeaveGrid {
+            grid_map: GridMap::new(columns, rows),
+        }
+    }
+
+    // The layout of the cells on top.
+    pub fn get_grid_map(&self) -> &GridMap {
+        &self.grid_map
+    }
+
+    // Returns whether there's a passage through the wall on one side of a cell,
+    // whether it's on top or through a tunnel.
+    pub fn is_wall_removed(
+        &self,
+        cell_pos: IVec2,
+        direction: Dir2,
+        removed_links: &HashSet<WeaveLink>,
+    ) -> bool {
+        let Some(neighbour_pos) = self.grid_map.neighbour_from_cell_pos(cell_pos, direction) else {
+            return false;
+        };
+        let Some(wall) = self.grid_map.inner_wall_from_cell_pos(cell_pos, direction) else {
+            return false;
+        };
+
+        [
+            WeaveLink::Wall(wall),
+            WeaveLink::Tunnel {
+                wall,
+                under: cell_pos,
+            },
+            WeaveLink::Tunnel {
+                wall,
+                under: neighbour_pos,
+            },
+        ]
+        .iter()
+        .any(|link| removed_links.contains(link))
+    }
+
+    // Returns whether a tunnel going in `direction` can go under a cell, which is
+    // when the cell is a straight corridor going across the tunnel and there's a
+    // cell on the far side for the tunnel to come out in.
+    pub fn can_tunnel_under(
+        &self,
+        cell_pos: IVec2,
+        direction: Dir2,
+        removed_links: &HashSet<WeaveLink>,
+    ) -> bool {
+        let (across, along) = match direction {
+            Dir2::NORTH | Dir2::SOUTH => ([Dir2::EAST, Dir2::WEST], [Dir2::NORTH, Dir2::SOUTH]),
+            _ => ([Dir2::NORTH, Dir2::SOUTH], [Dir2::EAST, Dir2::WEST]),
+        };
+
+        self.grid_map
+            .neighbour_from_cell_pos(cell_pos, direction)
+            .is_some()
+            && across
+                .into_iter()
+                .all(|side| self.is_wall_removed(cell_pos, side, removed_links))
+            && along
+                .into_iter()
+                .all(|side| !self.is_wall_removed(cell_pos, side, removed_links))
+    }
+
+    // The cells with a tunnel under them.
+    pub fn get_under_cells(&self, removed_links: &HashSet<WeaveLink>) -> HashSet<IVec2> {
+        removed_links
+            .iter()
+            .filter_map(|link| match link {
+                WeaveLink::Tunnel { under, .. } => Some(*under),
+                WeaveLink::Wall(_) => None,
+            })
+            .collect()
+    }
+
+    // Every wall with a passage through it, on top or through a tunnel, as walls
+    // of the grid map.
+    pub fn get_removed_walls(&self, removed_links: &HashSet<WeaveLink>) -> WallSet {
+        let mut removed_walls = self.grid_map.new_link_set();
+        removed_walls.extend(removed_links.iter().map(|link| match link {
+            WeaveLink::Wall(wall) => *wall,
+            WeaveLink::Tunnel { wall, .. } => *wall,
+        }));
+        removed_walls
+    }
+}
+
+impl Grid for WeaveGrid {
+    type Cell = IVec3;
+    type Link = WeaveLink;
+    type LinkSet = HashSet<WeaveLink>;
+
+    fn cell_count(&self) -> usize {
+        self.grid_map.cell_count()
+    }
+
+    // Only the cells on top, the tunnels under them are only part of the maze
+    // once they've been carved.
+    fn cells(&self) -> Box<dyn Iterator<Item = IVec3> + '_> {
+        Box::new(
+            self.grid_map
+                .iter_cells()
+                .map(|cell_pos| cell_pos.extend(0)),
+        )
+    }
+
+    // The tunnels' cells come after the cells on top.
+    fn cell_index(&self, cell: IVec3) -> Option<usize> {
+        if !(0..=1).contains(&cell.z) {
+            return None;
+        }
+
+        let top_index = self.grid_map.cell_index(cell.xy())?;
+        Some(cell.z as usize * self.grid_map.cell_index_count() + top_index)
+    }
+
+    fn cell_index_count(&self) -> usize {
+        self.grid_map.cell_index_count() * 2
+    }
+
+    fn random_cell(&self, rng: &mut Rng) -> Option<IVec3> {
+        Some(self.grid_map.random_cell_pos(rng)?.extend(0))
+    }
+
+    // A cell on top neighbours both the cell on top and the tunnel under each of
+    // its neighbours, if the tunnel has somewhere to come out. A tunnel neighbours
+    // the cells on top either side of it.
+    fn neighbours(&self, cell: IVec3) -> Vec<IVec3> {
+        let cell_pos = cell.xy();
+        match cell.z {
+            0 => DIRECTIONS
+                .into_iter()
+                .filter_map(|direction| {
+                    let neighbour_pos =
+                        self.grid_map.neighbour_from_cell_pos(cell_pos, direction)?;
+                    Some((neighbour_pos, direction))
+                })
+                .flat_map(|(neighbour_pos, direction)| {
+                    let has_far_side = self
+                        .grid_map
+                        .neighbour_from_cell_pos(neighbour_pos, direction)
+                        .is_some();
+                    let mut neighbours = vec![neighbour_pos.extend(0)];
+                    if has_far_side {
+                        neighbours.push(neighbour_pos.extend(1));
+                    }
+                    neighbours
+                })
+                .collect(),
+            1 => DIRECTIONS
+                .into_iter()
+                .filter(|direction| {
+                    self.grid_map
+                        .neighbour_from_cell_pos(cell_pos, -*direction)
+                        .is_some()
+                })
+                .filter_map(|direction| self.grid_map.neighbour_from_cell_pos(cell_pos, direction))
+                .map(|neighbour_pos| neighbour_pos.extend(0))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn wall_between(&self, from: IVec3, to: IVec3) -> Option<WeaveLink> {
+        if !self.neighbours(from).contains(&to) {
+            return None;
+        }
+
+        let wall = self
+            .grid_map
+            .inner_wall_between_cell_pos(from.xy(), to.xy())?;
+        match (from.z, to.z) {
+            (0, 0) => Some(WeaveLink::Wall(wall)),
+            (0, 1) => Some(WeaveLink::Tunnel {
+                wall,
+                under: to.xy(),
+            }),
+            (1, 0) => Some(WeaveLink::Tunnel {
+                wall,
+                under: from.xy(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn new_link_set(&self) -> HashSet<WeaveLink> {
+        HashSet::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::MazeBuilderRegistry;
+    use crate::utils::dijkstra;
+
+    fn get_wall(weave_grid: &WeaveGrid, from: IVec2, to: IVec2) -> Wall {
+        weave_grid
+            .get_grid_map()
+            .inner_wall_between_cell_pos(from, to)
+            .unwrap()
+    }
+
+    #[test]
+    fn tunnels_only_go_under_straight_corridors_going_across() {
+        let weave_grid = WeaveGrid::new(3, 3);
+        let center = IVec2::ONE;
+        let mut removed_links = HashSet::new();
+        assert!(!weave_grid.can_tunnel_under(center, Dir2::NORTH, &removed_links));
+
+        // A corridor going east to west can be tunnelled under going north or south.
+        removed_links.insert(WeaveLink::Wall(get_wall(
+            &weave_grid,
+            IVec2::new(0, 1),
+            center,
+        )));
+        removed_links.insert(WeaveLink::Wall(get_wall(
+            &weave_grid,
+            center,
+            IVec2::new(2, 1),
+        )));
+        assert!(weave_grid.can_tunnel_under(center, Dir2::NORTH, &removed_links));
+        assert!(weave_grid.can_tunnel_under(center, Dir2::SOUTH, &removed_links));
+        assert!(!weave_grid.can_tunnel_under(center, Dir2::EAST, &removed_links));
+
+        // Not once the corridor turns off to the north.
+        removed_links.insert(WeaveLink::Wall(get_wall(
+            &weave_grid,
+            center,
+            IVec2::new(1, 2),
+        )));
+        assert!(!weave_grid.can_tunnel_under(center, Dir2::SOUTH, &removed_links));
+
+        // A tunnel needs a cell on the far side to come out in.
+        let mut removed_links = HashSet::new();
+        let edge = IVec2::new(1, 2);
+        removed_links.insert(WeaveLink::Wall(get_wall(
+            &weave_grid,
+            IVec2::new(0, 2),
+            edge,
+        )));
+        removed_links.insert(WeaveLink::Wall(get_wall(
+            &weave_grid,
+            edge,
+            IVec2::new(2, 2),
+        )));
+        assert!(weave_grid.can_tunnel_under(edge, Dir2::SOUTH, &removed_links));
+        assert!(!weave_grid.can_tunnel_under(edge, Dir2::NORTH, &removed_links));
+    }
+
+    #[test]
+    fn tunnels_join_the_cells_on_top_either_side_of_them() {
+        let weave_grid = WeaveGrid::new(3, 3);
+        let tunnel = IVec3::new(1, 1, 1);
+        let mut tunnel_neighbours = weave_grid.neighbours(tunnel);
+        tunnel_neighbours.sort_by_key(|cell| (cell.x, cell.y));
+        assert_eq!(
+            tunnel_neighbours,
+            [
+                IVec3::new(0, 1, 0),
+                IVec3::new(1, 0, 0),
+                IVec3::new(1, 2, 0),
+                IVec3::new(2, 1, 0)
+            ]
+        );
+
+        // A corner has nowhere for a tunnel under it to come out.
+        assert!(weave_grid.neighbours(IVec3::new(0, 0, 1)).is_empty());
+        assert!(!weave_grid
+            .neighbours(IVec3::new(1, 0, 0))
+            .contains(&IVec3::new(0, 0, 1)));
+
+        let bottom = IVec2::new(1, 0);
+        let wall = get_wall(&weave_grid, bottom, IVec2::ONE);
+        let tunnel_link = WeaveLink::Tunnel {
+            wall,
+            under: IVec2::ONE,
+        };
+        assert_eq!(
+            weave_grid.wall_between(bottom.extend(0), tunnel),
+            Some(tunnel_link)
+        );
+        assert_eq!(
+            weave_grid.wall_between(tunnel, bottom.extend(0)),
+            Some(tunnel_link)
+        );
+        assert_eq!(weave_grid.wall_between(tunnel, IVec3::new(1, 2, 1)), None);
+
+        for cell_pos in weave_grid.get_grid_map().iter_cells() {
+            for cell in [cell_pos.extend(0), cell_pos.extend(1)] {
+                for neighbour in weave_grid.neighbours(cell) {
+                    assert!(weave_grid.neighbours(neighbour).contains(&cell));
+                    assert_eq!(
+                        weave_grid.wall_between(cell, neighbour),
+                        weave_grid.wall_between(neighbour, cell)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn carved_tunnels_go_straight_under_a_corridor_and_reach_both_ends() {
+        let weave_grid = WeaveGrid::new(8, 8);
+        let mut tunnel_count = 0;
+
+        for builder in MazeBuilderRegistry::default().iter() {
+            for seed in 0..5 {
+                let mut rng = Rng::with_seed(seed);
+                let Some(removed_links) = builder.carve_weave_grid(&weave_grid, &mut rng) else {
+                    continue;
+                };
+                let name = builder.name();
+                let under_cells = weave_grid.get_under_cells(&removed_links);
+                tunnel_count += under_cells.len();
+
+                // The tunnels are cells of their own, and the maze is still a tree.
+                let cell_count = weave_grid.cell_count() + under_cells.len();
+                let distances = dijkstra(IVec3::ZERO, &weave_grid, &removed_links);
+                assert_eq!(removed_links.len(), cell_count - 1, "{name}");
+                assert_eq!(distances.len(), cell_count, "{name}");
+
+                for under in under_cells {
+                    let sides: Vec<Dir2> = DIRECTIONS
+                        .into_iter()
+                        .filter(|direction| {
+                            let wall = weave_grid
+                                .get_grid_map()
+                                .inner_wall_from_cell_pos(under, *direction);
+                            wall.is_some_and(|wall| {
+                                removed_links.contains(&WeaveLink::Tunnel { wall, under })
+                            })
+                        })
+                        .collect();
+                    assert_eq!(sides.len(), 2, "{name}");
+                    assert_eq!(sides[0], -sides[1], "{name}");
+
+                    // The cell on top goes across the tunnel, maybe into another
+                    // tunnel, and its only passages along the tunnel are the tunnel.
+                    let across = match sides[0] {
+                        Dir2::NORTH | Dir2::SOUTH => Dir2::EAST,
+                        _ => Dir2::NORTH,
+                    };
+                    for direction in [across, -across] {
+                        assert!(
+                            weave_grid.is_wall_removed(under, direction, &removed_links),
+                            "{name}"
+                        );
+                    }
+                    for direction in sides {
+                        let neighbour = weave_grid
+                            .get_grid_map()
+                            .neighbour_from_cell_pos(under, direction)
+                            .unwrap();
+                        let wall = get_wall(&weave_grid, under, neighbour);
+                        assert!(!removed_links.contains(&WeaveLink::Wall(wall)), "{name}");
+                        let tunnel = WeaveLink::Tunnel {
+                            wall,
+                            under: neighbour,
+                        };
+                        assert!(!removed_links.contains(&tunnel), "{name}");
+                    }
+                }
+            }
+        }
+
+        assert!(tunnel_count > 0);
+    }
+
+    #[test]
+    fn removed_walls_match_the_passages_on_top_and_through_tunnels() {
+        let weave_grid = WeaveGrid::new(8, 8);
+        let grid_map = weave_grid.get_grid_map();
+
+        for builder in MazeBuilderRegistry::default().iter() {
+            let mut rng = Rng::with_seed(0);
+            let Some(removed_links) = builder.carve_weave_grid(&weave_grid, &mut rng) else {
+                continue;
+            };
+            let removed_walls = weave_grid.get_removed_walls(&removed_links);
+            assert_eq!(
+                removed_walls.len(),
+                removed_links.len(),
+                "{}",
+                builder.name()
+            );
+
+            for cell_pos in grid_map.iter_cells() {
+                for direction in DIRECTIONS {
+                    let Some(wall) = grid_map.inner_wall_from_cell_pos(cell_pos, direction) else {
+                        continue;
+                    };
+                    assert_eq!(
+                        removed_walls.contains(&wall),
+                        weave_grid.is_wall_removed(cell_pos, direction, &removed_links),
+                        "{}",
+                        builder.name()
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use crate::ui::*;
+use bevy::prelude::*;
+
+pub fn despawn_grid_map(mut commands: Commands, grid_map_query: Query<Entity, With<GridMapRoot>>) {
+    for grid_map_entity in &grid_map_query {
+        commands.entity(grid_map_entity).despawn_recursive();
+    }
+}
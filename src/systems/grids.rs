@@ -0,0 +1,36 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use mazes::components::Wall;
+use mazes::resources::*;
+
+// Every grid a maze can be carved into, the `GridType` resource picks which one
+// is used.
+#[derive(SystemParam)]
+pub struct Grids<'w> {
+    pub grid_map: Res<'w, GridMap>,
+    pub polar_grid: Res<'w, PolarGrid>,
+    pub hex_grid: Res<'w, HexGrid>,
+    pub triangle_grid: Res<'w, TriangleGrid>,
+    pub upsilon_grid: Res<'w, UpsilonGrid>,
+    pub layered_grid: Res<'w, LayeredGrid>,
+    pub weave_grid: Res<'w, WeaveGrid>,
+}
+
+impl Grids<'_> {
+    // The grid whose cells are on screen. The levels of a layered maze and the top
+    // of a weave maze are drawn as square grid maps.
+    pub fn get_drawn_grid(
+        &self,
+        grid_type: GridType,
+    ) -> &dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet> {
+        match grid_type {
+            GridType::Square => &*self.grid_map,
+            GridType::Polar => &*self.polar_grid,
+            GridType::Hex => &*self.hex_grid,
+            GridType::Triangle => &*self.triangle_grid,
+            GridType::Upsilon => &*self.upsilon_grid,
+            GridType::Layered => self.layered_grid.get_grid_map(),
+            GridType::Weave => self.weave_grid.get_grid_map(),
+        }
+    }
+}
@@ -1,13 +1,25 @@
-use crate::components::*;
-use crate::resources::*;
+use crate::ui::*;
 use bevy::prelude::*;
-use fastrand;
+use mazes::maze_builders::{BinaryTreeBias, GrowingTreeStrategy, SidewinderBias};
+use mazes::maze_post_processors::{Braid, Sparseness};
+use mazes::resources::*;
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_hud_action(
     interaction_query: Query<(&Interaction, &HudAction), (Changed<Interaction>, With<Button>)>,
     mut overlay_state: ResMut<OverlayState>,
+    mut living_maze: ResMut<LivingMaze>,
     mut rng_seed: ResMut<RngSeed>,
-    mut maze_builder_type: ResMut<MazeBuilderType>,
+    mut selected_maze_builder: ResMut<SelectedMazeBuilder>,
+    mut grid_type: ResMut<GridType>,
+    mut grid_map: ResMut<GridMap>,
+    layered_grid: Res<LayeredGrid>,
+    mut visible_level: ResMut<VisibleLevel>,
+    mut binary_tree_bias: ResMut<BinaryTreeBias>,
+    mut sidewinder_bias: ResMut<SidewinderBias>,
+    mut growing_tree_strategy: ResMut<GrowingTreeStrategy>,
+    mut braid: ResMut<Braid>,
+    mut sparseness: ResMut<Sparseness>,
 ) {
     for (interaction, hud_action) in &interaction_query {
         if *interaction == Interaction::Pressed {
@@ -15,11 +27,54 @@ pub fn handle_hud_action(
                 HudAction::ToggleOverlay => {
                     overlay_state.0 = !overlay_state.0;
                 }
+                HudAction::ToggleLivingMaze => {
+                    living_maze.is_enabled = !living_maze.is_enabled;
+                }
                 HudAction::RandomiseSeed => {
                     rng_seed.0 = fastrand::u64(..u64::MAX);
                 }
-                HudAction::ChangeMazeType(new_maze_type) => {
-                    *maze_builder_type = *new_maze_type;
+                HudAction::ChangeMazeBuilder(new_maze_builder) => {
+                    selected_maze_builder.0 = new_maze_builder.clone();
+                }
+                HudAction::ChangeGridType(new_grid_type) => {
+                    *grid_type = *new_grid_type;
+                }
+                HudAction::ChangeWrapMode(new_wrap_mode) => {
+                    grid_map.set_wrap_mode(*new_wrap_mode);
+                }
+                // Levels are only changed when there's more than one to show.
+                HudAction::LevelDown => {
+                    if *grid_type == GridType::Layered && visible_level.0 > 0 {
+                        visible_level.0 -= 1;
+                    }
+                }
+                HudAction::LevelUp => {
+                    if *grid_type == GridType::Layered
+                        && visible_level.0 < layered_grid.get_levels() - 1
+                    {
+                        visible_level.0 += 1;
+                    }
+                }
+                HudAction::ChangeBinaryTreeDiagonal(new_diagonal) => {
+                    binary_tree_bias.diagonal = *new_diagonal;
+                }
+                HudAction::ChangeBinaryTreeProbability(new_probability) => {
+                    binary_tree_bias.vertical_probability = *new_probability;
+                }
+                HudAction::ChangeSidewinderRunOrientation(new_run_orientation) => {
+                    sidewinder_bias.run_orientation = *new_run_orientation;
+                }
+                HudAction::ChangeSidewinderProbability(new_probability) => {
+                    sidewinder_bias.run_probability = *new_probability;
+                }
+                HudAction::ChangeGrowingTreeStrategy(new_strategy) => {
+                    *growing_tree_strategy = *new_strategy;
+                }
+                HudAction::ChangeBraidProbability(new_probability) => {
+                    braid.dead_end_probability = *new_probability;
+                }
+                HudAction::ChangeSparseness(new_sparseness) => {
+                    *sparseness = *new_sparseness;
                 }
                 HudAction::None => (),
             }
@@ -1,16 +1,25 @@
 use bevy::color::palettes::css::*;
 use bevy::prelude::*;
+use bevy::render::{
+    mesh::{Indices, PrimitiveTopology},
+    render_asset::RenderAssetUsages,
+};
+
+const INACCESSIBLE_CELL_COLOR: Color = Color::srgb(0.35, 0.3, 0.25);
 
 pub fn get_cell_background_color(
-    distance: Option<&i32>,
+    distance: Option<i32>,
     farthest_distance: i32,
     has_overlay: bool,
+    is_inaccessible: bool,
 ) -> Color {
-    if !has_overlay {
+    if is_inaccessible {
+        INACCESSIBLE_CELL_COLOR
+    } else if !has_overlay {
         Color::srgba(0.8, 0.8, 0.8, 1.0)
     } else if let Some(distance) = distance {
         let max = farthest_distance as f32;
-        let dist = *distance as f32;
+        let dist = distance as f32;
         let intensity = (max - dist) / max;
         let dark = 1.0 * intensity;
         let bright = 0.5 + (0.5 * intensity);
@@ -23,7 +32,7 @@ pub fn get_cell_background_color(
 pub fn get_cell_text(
     is_start: bool,
     is_end: bool,
-    distance: Option<&i32>,
+    distance: Option<i32>,
     has_overlay: bool,
 ) -> String {
     if !has_overlay {
@@ -46,3 +55,52 @@ pub fn get_cell_text_color(is_on_path: bool) -> Color {
         Color::Srgba(BLACK)
     }
 }
+
+// A filled mesh for an outline going counter-clockwise, made of triangles fanning
+// out from the origin, so the origin has to be able to see every edge.
+pub fn get_polygon_mesh(outline: &[Vec2]) -> Mesh {
+    let positions: Vec<[f32; 3]> = std::iter::once(Vec2::ZERO)
+        .chain(outline.iter().copied())
+        .map(|point| [point.x, point.y, 0.0])
+        .collect();
+    let point_count = outline.len() as u32;
+    let indices: Vec<u32> = (0..point_count)
+        .flat_map(|index| [0, index + 1, (index + 1) % point_count + 1])
+        .collect();
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+// A mesh for a line `width` thick going through each point. Each segment is a
+// rectangle that overlaps the next one by half the width, so there are no gaps
+// where the line bends.
+pub fn get_line_mesh(points: &[Vec2], width: f32) -> Mesh {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut indices: Vec<u32> = vec![];
+
+    for segment in points.windows(2) {
+        let direction = (segment[1] - segment[0]).normalize_or_zero() * width / 2.0;
+        let normal = direction.perp();
+        let start = segment[0] - direction;
+        let end = segment[1] + direction;
+
+        let first_index = positions.len() as u32;
+        positions.extend(
+            [start - normal, end - normal, end + normal, start + normal]
+                .map(|point| [point.x, point.y, 0.0]),
+        );
+        indices.extend([0, 1, 2, 0, 2, 3].map(|index| first_index + index));
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(indices))
+}
@@ -1,18 +1,36 @@
+mod despawn_grid_map;
+mod grids;
 mod handle_hud_action;
 mod map_utils;
 mod setup_camera;
 mod setup_grid_map;
 mod setup_hud;
 mod update_cell_content;
+mod update_grid_type_settings_visibility;
+mod update_living_maze;
+mod update_maze_builder_description;
+mod update_maze_builder_dropdown;
+mod update_maze_builder_registry;
+mod update_maze_builder_settings_visibility;
 mod update_maze_resources;
 mod update_overlay_visibility;
+mod update_visible_level;
 mod update_wall_visibility;
 
+pub use despawn_grid_map::*;
+pub use grids::*;
 pub use handle_hud_action::*;
 pub use setup_camera::*;
 pub use setup_grid_map::*;
 pub use setup_hud::*;
 pub use update_cell_content::*;
+pub use update_grid_type_settings_visibility::*;
+pub use update_living_maze::*;
+pub use update_maze_builder_description::*;
+pub use update_maze_builder_dropdown::*;
+pub use update_maze_builder_registry::*;
+pub use update_maze_builder_settings_visibility::*;
 pub use update_maze_resources::*;
 pub use update_overlay_visibility::*;
+pub use update_visible_level::*;
 pub use update_wall_visibility::*;
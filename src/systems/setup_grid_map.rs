@@ -1,18 +1,42 @@
 use super::map_utils::*;
-use crate::components::*;
-use crate::resources::*;
+use super::Grids;
+use crate::ui::*;
 use bevy::prelude::*;
+use mazes::components::*;
+use mazes::resources::*;
 
 const POINT_SIZE: f32 = 0.12;
 const WALL_SIZE: f32 = 0.08;
 const PADDING_PX: f32 = 75.0;
 const COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+const SEAM_MARKER_SIZE: f32 = 0.15;
+// How far outside the edge of the grid map the seam markers are drawn.
+const SEAM_MARKER_OFFSET: f32 = 0.35;
+const STAIR_MARKER_SIZE: f32 = 0.12;
+const STAIR_MARKER_COLOR: Color = Color::srgb(0.2, 0.4, 0.8);
+// How far in from the sides of a crossing its passage on top is drawn, leaving a
+// gap where the tunnel goes under it.
+const CROSSING_INSET: f32 = 0.2;
 
+// The resources that decide how the cells and walls of the maze are drawn.
+struct MazeView<'a> {
+    removed_walls: &'a RemovedWalls,
+    inaccessible_cells: &'a InaccessibleCells,
+    solution: &'a Solution,
+    has_overlay: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn setup_grid_map(
     mut commands: Commands,
-    grid_map: Res<GridMap>,
+    grid_type: Res<GridType>,
+    grids: Grids,
+    layered_maze: Res<LayeredMaze>,
+    visible_level: Res<VisibleLevel>,
+    weave_maze: Res<WeaveMaze>,
     overlay_state: Res<OverlayState>,
     removed_walls: Res<RemovedWalls>,
+    inaccessible_cells: Res<InaccessibleCells>,
     solution: Res<Solution>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
@@ -26,147 +50,378 @@ pub fn setup_grid_map(
         y: window_height - (PADDING_PX * 2.0),
     };
 
-    let has_overlay = overlay_state.0;
+    let maze_view = MazeView {
+        removed_walls: &removed_walls,
+        inaccessible_cells: &inaccessible_cells,
+        solution: &solution,
+        has_overlay: overlay_state.0,
+    };
+
+    match *grid_type {
+        GridType::Square => {
+            let grid_entity = spawn_square_grid(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &grids.grid_map,
+                &maze_view,
+                available_space,
+            );
+            spawn_seam_markers(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &grids.grid_map,
+                &removed_walls,
+                grid_entity,
+            );
+        }
+        GridType::Layered => {
+            // Each level is drawn as a square grid map, the resources only hold the
+            // walls, cells and solution of the level that's shown.
+            let level_grid_map = grids.layered_grid.get_grid_map();
+            let grid_entity = spawn_square_grid(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                level_grid_map,
+                &maze_view,
+                available_space,
+            );
+            spawn_stair_markers(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &grids.layered_grid,
+                &layered_maze,
+                visible_level.0,
+                grid_entity,
+            );
+        }
+        GridType::Weave => {
+            // The passages on top and through the tunnels have both removed walls,
+            // the crossings are drawn over the cells they're at.
+            let weave_grid_map = grids.weave_grid.get_grid_map();
+            let grid_entity = spawn_square_grid(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                weave_grid_map,
+                &maze_view,
+                available_space,
+            );
+            spawn_weave_crossings(
+                &mut commands,
+                &mut meshes,
+                &mut materials,
+                &grids.weave_grid,
+                &weave_maze,
+                grid_entity,
+            );
+        }
+        GridType::Polar => spawn_drawable_grid(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &maze_view,
+            available_space,
+            &*grids.polar_grid,
+        ),
+        GridType::Hex => spawn_drawable_grid(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &maze_view,
+            available_space,
+            &*grids.hex_grid,
+        ),
+        GridType::Triangle => spawn_drawable_grid(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &maze_view,
+            available_space,
+            &*grids.triangle_grid,
+        ),
+        GridType::Upsilon => spawn_drawable_grid(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &maze_view,
+            available_space,
+            &*grids.upsilon_grid,
+        ),
+    }
+}
+
+// Draws a square grid map, returning the entity everything is a child of.
+fn spawn_square_grid(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    grid_map: &GridMap,
+    maze_view: &MazeView,
+    available_space: Vec2,
+) -> Entity {
     let scale = grid_map.get_scale_from_available_space(available_space);
-    let start_pos = grid_map.get_centered_grid_pos(scale);
+    let grid_entity = spawn_grid_map_root(commands, scale, grid_map.get_centered_grid_pos(scale));
     let point_shape = meshes.add(Circle::new(POINT_SIZE));
     let rectangle_shape = meshes.add(Rectangle::new(1.0, 1.0));
     let material = materials.add(COLOR);
 
-    let grid_entity = commands
-        .spawn((
-            Transform::from_scale(Vec3 {
-                x: scale,
-                y: scale,
-                z: 1.0,
-            })
-            .with_translation(Vec3 {
-                x: start_pos.x,
-                y: start_pos.y,
-                z: 0.0,
-            }),
-            InheritedVisibility::VISIBLE,
-        ))
-        .id();
+    // Cells that are turned off aren't drawn, along with any points and walls that
+    // are only around cells that are turned off.
+    grid_map
+        .iter_points()
+        .filter(|point_pos| grid_map.is_point_next_to_enabled_cell(*point_pos))
+        .for_each(|point_pos| {
+            let point_entity = commands
+                .spawn((
+                    Mesh2d(point_shape.clone()),
+                    MeshMaterial2d(material.clone()),
+                    Transform::from_translation(point_pos.as_vec2().extend(0.0)),
+                ))
+                .id();
 
-    grid_map.iter_points().for_each(|point_pos| {
-        let point_entity = commands
-            .spawn((
-                Mesh2d(point_shape.clone()),
-                MeshMaterial2d(material.clone()),
-                Transform::from_translation(point_pos.as_vec2().extend(0.0)),
-            ))
-            .id();
+            commands.entity(grid_entity).add_child(point_entity);
+        });
 
-        commands.entity(grid_entity).add_child(point_entity);
+    grid_map.iter_cells().for_each(|cell_position| {
+        let cell_entity = spawn_cell(
+            commands,
+            materials,
+            maze_view,
+            cell_position,
+            maze_view.solution.distances.get(grid_map, cell_position),
+            // The rectangle will be centered over the cell's position,
+            // so we need to move it by another half of its length.
+            cell_position.as_vec2() + 0.5,
+            rectangle_shape.clone(),
+        );
+        commands.entity(grid_entity).add_child(cell_entity);
     });
 
-    grid_map.iter_cells().for_each(|cell_position| {
-        let translation = cell_position.as_vec2();
-        let distance = solution.distances.get(&cell_position);
+    spawn_square_walls(
+        commands,
+        grid_map,
+        maze_view.removed_walls,
+        grid_entity,
+        rectangle_shape,
+        material,
+    );
+    grid_entity
+}
 
-        let cell_entity = commands
-            .spawn((
-                Cell {
-                    position: cell_position,
-                },
-                Transform::from_scale(Vec3::ONE).with_translation(Vec3 {
-                    // The rectangle will be centered over the 'from' position,
-                    // so we need to move it by another half of its length.
-                    x: translation.x + 0.5,
-                    y: translation.y + 0.5,
-                    z: -0.1,
-                }),
-                Visibility::Visible,
-            ))
-            .id();
+// Draws a grid that isn't a square grid map, where each cell is a polygon and
+// each wall is a line through the wall's outline.
+fn spawn_drawable_grid(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    maze_view: &MazeView,
+    available_space: Vec2,
+    grid: &dyn DrawableGrid,
+) {
+    let scale = grid.get_scale_from_available_space(available_space);
+    let grid_entity = spawn_grid_map_root(commands, scale, grid.get_centered_grid_pos(scale));
+    let material = materials.add(COLOR);
 
-        let cell_contents_visibility = if has_overlay {
+    grid.cells().for_each(|cell_position| {
+        let center = grid.get_cell_center(cell_position);
+        let outline: Vec<Vec2> = grid
+            .get_cell_outline(cell_position)
+            .into_iter()
+            .map(|point| point - center)
+            .collect();
+        let cell_entity = spawn_cell(
+            commands,
+            materials,
+            maze_view,
+            cell_position,
+            maze_view.solution.distances.get(grid, cell_position),
+            center,
+            meshes.add(get_polygon_mesh(&outline)),
+        );
+        commands.entity(grid_entity).add_child(cell_entity);
+    });
+
+    grid.get_walls().into_iter().for_each(|wall| {
+        let visibility = if maze_view.removed_walls.0.contains(&wall) {
             Visibility::Hidden
         } else {
             Visibility::Visible
         };
-        let cell_background_color =
-            get_cell_background_color(distance, solution.farthest_distance, false);
-        let cell_background_material = materials.add(cell_background_color);
-        let cell_background_entity = commands
-            .spawn((
-                CellContentBackground,
-                OverlayVisibility(false),
-                Mesh2d(rectangle_shape.clone()),
-                MeshMaterial2d(cell_background_material),
-                Transform::from_scale(Vec3::ONE),
-                cell_contents_visibility,
-            ))
-            .id();
-        let is_start = cell_position == solution.start;
-        let is_end = cell_position == solution.end;
-        let is_on_path = solution.path.contains_key(&cell_position);
-        let cell_text_color = get_cell_text_color(is_on_path);
-        let cell_text = get_cell_text(is_start, is_end, distance, false);
-        let cell_text_entity = commands
-            .spawn((
-                CellContentText,
-                OverlayVisibility(false),
-                Text2d(cell_text),
-                TextColor(cell_text_color.clone()),
-                Transform::from_scale(Vec3 {
-                    x: 0.02,
-                    y: 0.02,
-                    z: 1.0,
-                }),
-                cell_contents_visibility,
-            ))
-            .id();
+        let wall_shape = meshes.add(get_line_mesh(&grid.get_wall_outline(wall), WALL_SIZE));
 
-        let overlay_visibility = if has_overlay {
-            Visibility::Visible
-        } else {
-            Visibility::Hidden
-        };
-        let overlay_background_color =
-            get_cell_background_color(distance, solution.farthest_distance, true);
-        let overlay_background_material = materials.add(overlay_background_color);
-        let overlay_background_entity = commands
+        let wall_entity = commands
             .spawn((
-                CellOverlayBackground,
-                OverlayVisibility(true),
-                Mesh2d(rectangle_shape.clone()),
-                MeshMaterial2d(overlay_background_material),
-                Transform::from_scale(Vec3::ONE),
-                overlay_visibility,
-            ))
-            .id();
-        let text_color = get_cell_text_color(is_on_path);
-        let overlay_text = get_cell_text(is_start, is_end, distance, true);
-        let overlay_text_entity = commands
-            .spawn((
-                CellOverlayText,
-                OverlayVisibility(true),
-                Text2d(overlay_text),
-                TextColor(text_color),
-                Transform::from_scale(Vec3 {
-                    x: 0.02,
-                    y: 0.02,
-                    z: 1.0,
-                }),
-                overlay_visibility,
+                wall,
+                Mesh2d(wall_shape),
+                MeshMaterial2d(material.clone()),
+                visibility,
+                Transform::default(),
             ))
             .id();
-
-        commands.entity(cell_entity).add_children(&[
-            cell_background_entity,
-            cell_text_entity,
-            overlay_background_entity,
-            overlay_text_entity,
-        ]);
-        commands.entity(grid_entity).add_child(cell_entity);
+        commands.entity(grid_entity).add_child(wall_entity);
     });
 
+    // The walls around the outside are never removed, so they don't need a `Wall`.
+    grid.get_boundary_outlines()
+        .into_iter()
+        .for_each(|boundary_outline| {
+            let boundary_entity = commands
+                .spawn((
+                    Mesh2d(meshes.add(get_line_mesh(&boundary_outline, WALL_SIZE))),
+                    MeshMaterial2d(material.clone()),
+                    Transform::default(),
+                ))
+                .id();
+            commands.entity(grid_entity).add_child(boundary_entity);
+        });
+}
+
+fn spawn_grid_map_root(commands: &mut Commands, scale: f32, translation: Vec2) -> Entity {
+    commands
+        .spawn((
+            GridMapRoot,
+            Transform::from_scale(Vec3 {
+                x: scale,
+                y: scale,
+                z: 1.0,
+            })
+            .with_translation(translation.extend(0.0)),
+            InheritedVisibility::VISIBLE,
+        ))
+        .id()
+}
+
+// Spawns a cell with its content and overlay as children, in the order that
+// `update_cell_content` expects them.
+fn spawn_cell(
+    commands: &mut Commands,
+    materials: &mut Assets<ColorMaterial>,
+    maze_view: &MazeView,
+    cell_position: IVec2,
+    distance: Option<i32>,
+    translation: Vec2,
+    cell_shape: Handle<Mesh>,
+) -> Entity {
+    let MazeView {
+        inaccessible_cells,
+        solution,
+        has_overlay,
+        ..
+    } = *maze_view;
+    let is_inaccessible = inaccessible_cells.0.contains(&cell_position);
+
+    let cell_entity = commands
+        .spawn((
+            Cell {
+                position: cell_position,
+            },
+            Transform::from_translation(translation.extend(-0.1)),
+            Visibility::Visible,
+        ))
+        .id();
+
+    let cell_contents_visibility = if has_overlay {
+        Visibility::Hidden
+    } else {
+        Visibility::Visible
+    };
+    let cell_background_color =
+        get_cell_background_color(distance, solution.farthest_distance, false, is_inaccessible);
+    let cell_background_material = materials.add(cell_background_color);
+    let cell_background_entity = commands
+        .spawn((
+            CellContentBackground,
+            OverlayVisibility(false),
+            Mesh2d(cell_shape.clone()),
+            MeshMaterial2d(cell_background_material),
+            Transform::from_scale(Vec3::ONE),
+            cell_contents_visibility,
+        ))
+        .id();
+    let is_start = cell_position == solution.start;
+    let is_end = cell_position == solution.end;
+    let is_on_path = solution.path.contains_key(&cell_position);
+    let cell_text_color = get_cell_text_color(is_on_path);
+    let cell_text = get_cell_text(is_start, is_end, distance, false);
+    let cell_text_entity = commands
+        .spawn((
+            CellContentText,
+            OverlayVisibility(false),
+            Text2d(cell_text),
+            TextColor(cell_text_color),
+            Transform::from_scale(Vec3 {
+                x: 0.02,
+                y: 0.02,
+                z: 1.0,
+            }),
+            cell_contents_visibility,
+        ))
+        .id();
+
+    let overlay_visibility = if has_overlay {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    let overlay_background_color =
+        get_cell_background_color(distance, solution.farthest_distance, true, is_inaccessible);
+    let overlay_background_material = materials.add(overlay_background_color);
+    let overlay_background_entity = commands
+        .spawn((
+            CellOverlayBackground,
+            OverlayVisibility(true),
+            Mesh2d(cell_shape.clone()),
+            MeshMaterial2d(overlay_background_material),
+            Transform::from_scale(Vec3::ONE),
+            overlay_visibility,
+        ))
+        .id();
+    let text_color = get_cell_text_color(is_on_path);
+    let overlay_text = get_cell_text(is_start, is_end, distance, true);
+    let overlay_text_entity = commands
+        .spawn((
+            CellOverlayText,
+            OverlayVisibility(true),
+            Text2d(overlay_text),
+            TextColor(text_color),
+            Transform::from_scale(Vec3 {
+                x: 0.02,
+                y: 0.02,
+                z: 1.0,
+            }),
+            overlay_visibility,
+        ))
+        .id();
+
+    commands.entity(cell_entity).add_children(&[
+        cell_background_entity,
+        cell_text_entity,
+        overlay_background_entity,
+        overlay_text_entity,
+    ]);
+    cell_entity
+}
+
+fn spawn_square_walls(
+    commands: &mut Commands,
+    grid_map: &GridMap,
+    removed_walls: &RemovedWalls,
+    grid_entity: Entity,
+    rectangle_shape: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+) {
     grid_map
         .iter_walls(WallOrientation::Horizontal)
+        .filter(|wall| grid_map.is_wall_next_to_enabled_cell(*wall))
         .for_each(|wall| {
             let from = wall.from.as_vec2();
+            // The wall along the north edge of a grid map that wraps around is drawn
+            // as the seam on the south edge, so that both are hidden together.
+            let wall = grid_map.wrap_wall(wall);
             let visibility = if removed_walls.0.contains(&wall) {
                 Visibility::Hidden
             } else {
@@ -200,8 +455,11 @@ pub fn setup_grid_map(
 
     grid_map
         .iter_walls(WallOrientation::Vertical)
+        .filter(|wall| grid_map.is_wall_next_to_enabled_cell(*wall))
         .for_each(|wall| {
             let from = wall.from.as_vec2();
+            // Likewise for the east edge and the seam on the west edge.
+            let wall = grid_map.wrap_wall(wall);
             let visibility = if removed_walls.0.contains(&wall) {
                 Visibility::Hidden
             } else {
@@ -233,3 +491,204 @@ pub fn setup_grid_map(
             commands.entity(grid_entity).add_child(wall_entity);
         });
 }
+
+// Draws a pair of markers outside each seam of a grid map that wraps around, one
+// on each edge, which are shown when there's a passage through the seam. Each
+// pair has its own colour so that matching ends can be found.
+fn spawn_seam_markers(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    grid_map: &GridMap,
+    removed_walls: &RemovedWalls,
+    grid_entity: Entity,
+) {
+    let marker_shape = meshes.add(Circle::new(SEAM_MARKER_SIZE));
+    let columns = grid_map.get_columns() as f32;
+    let rows = grid_map.get_rows() as f32;
+
+    grid_map
+        .iter_walls(WallOrientation::Horizontal)
+        .chain(grid_map.iter_walls(WallOrientation::Vertical))
+        .filter(|wall| grid_map.is_seam_wall(*wall))
+        .filter(|wall| grid_map.cells_from_inner_wall(*wall).is_some())
+        .for_each(|wall| {
+            let from = wall.from.as_vec2();
+            let (hue, positions) = if wall.from.y == wall.to.y {
+                (
+                    from.x / columns,
+                    [
+                        Vec2::new(from.x + 0.5, -SEAM_MARKER_OFFSET),
+                        Vec2::new(from.x + 0.5, rows + SEAM_MARKER_OFFSET),
+                    ],
+                )
+            } else {
+                (
+                    from.y / rows,
+                    [
+                        Vec2::new(-SEAM_MARKER_OFFSET, from.y + 0.5),
+                        Vec2::new(columns + SEAM_MARKER_OFFSET, from.y + 0.5),
+                    ],
+                )
+            };
+            let material = materials.add(Color::hsl(hue * 360.0, 0.7, 0.5));
+            let visibility = if removed_walls.0.contains(&wall) {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+
+            positions.into_iter().for_each(|position| {
+                let marker_entity = commands
+                    .spawn((
+                        SeamMarker(wall),
+                        Mesh2d(marker_shape.clone()),
+                        MeshMaterial2d(material.clone()),
+                        visibility,
+                        Transform::from_translation(position.extend(0.0)),
+                    ))
+                    .id();
+
+                commands.entity(grid_entity).add_child(marker_entity);
+            });
+        });
+}
+
+// Marks the cells of the level that's shown that have stairs, with a triangle
+// pointing up in the north east corner for stairs up, and one pointing down in
+// the south west corner for stairs down. Also labels the level above the grid.
+fn spawn_stair_markers(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    layered_grid: &LayeredGrid,
+    layered_maze: &LayeredMaze,
+    level: i32,
+    grid_entity: Entity,
+) {
+    let grid_map = layered_grid.get_grid_map();
+    let up_shape = meshes.add(Triangle2d::new(
+        Vec2::new(0.0, STAIR_MARKER_SIZE),
+        Vec2::new(-STAIR_MARKER_SIZE, -STAIR_MARKER_SIZE),
+        Vec2::new(STAIR_MARKER_SIZE, -STAIR_MARKER_SIZE),
+    ));
+    let down_shape = meshes.add(Triangle2d::new(
+        Vec2::new(0.0, -STAIR_MARKER_SIZE),
+        Vec2::new(STAIR_MARKER_SIZE, STAIR_MARKER_SIZE),
+        Vec2::new(-STAIR_MARKER_SIZE, STAIR_MARKER_SIZE),
+    ));
+    let material = materials.add(STAIR_MARKER_COLOR);
+
+    grid_map.iter_cells().for_each(|cell_position| {
+        let cell = cell_position.extend(level);
+        let center = cell_position.as_vec2() + 0.5;
+        let stairs = [
+            (
+                LayeredLink::Stairs(cell),
+                up_shape.clone(),
+                Vec2::splat(0.3),
+            ),
+            (
+                LayeredLink::Stairs(cell - IVec3::Z),
+                down_shape.clone(),
+                Vec2::splat(-0.3),
+            ),
+        ];
+
+        stairs
+            .into_iter()
+            .filter(|(link, _, _)| layered_maze.removed_links.contains(link))
+            .for_each(|(_, shape, offset)| {
+                let marker_entity = commands
+                    .spawn((
+                        Mesh2d(shape),
+                        MeshMaterial2d(material.clone()),
+                        Transform::from_translation((center + offset).extend(0.0)),
+                    ))
+                    .id();
+                commands.entity(grid_entity).add_child(marker_entity);
+            });
+    });
+
+    let label_entity = commands
+        .spawn((
+            Text2d(format!(
+                "Level {} of {}",
+                level + 1,
+                layered_grid.get_levels()
+            )),
+            TextColor(COLOR),
+            Transform::from_scale(Vec3 {
+                x: 0.02,
+                y: 0.02,
+                z: 1.0,
+            })
+            .with_translation(Vec3 {
+                x: grid_map.get_columns() as f32 / 2.0,
+                y: grid_map.get_rows() as f32 + 0.5,
+                z: 0.0,
+            }),
+        ))
+        .id();
+    commands.entity(grid_entity).add_child(label_entity);
+}
+
+// Draws each crossing as a bridge, a pair of walls inset from the sides of the
+// cell along the passage on top. The walls of the tunnel stop short of the
+// bridge, leaving a gap that shows the tunnel going under it.
+fn spawn_weave_crossings(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    weave_grid: &WeaveGrid,
+    weave_maze: &WeaveMaze,
+    grid_entity: Entity,
+) {
+    let grid_map = weave_grid.get_grid_map();
+    let material = materials.add(COLOR);
+
+    weave_grid
+        .get_under_cells(&weave_maze.removed_links)
+        .into_iter()
+        .for_each(|cell_position| {
+            let corner = cell_position.as_vec2();
+            let is_top_vertical = grid_map
+                .inner_wall_from_cell_pos(cell_position, Dir2::NORTH)
+                .is_some_and(|wall| weave_maze.removed_links.contains(&WeaveLink::Wall(wall)));
+
+            // The bridge, then the tunnel walls either side of it, as points from
+            // the cell's south west corner for a passage on top going north.
+            let lines = [
+                [
+                    Vec2::new(CROSSING_INSET, 0.0),
+                    Vec2::new(CROSSING_INSET, 1.0),
+                ],
+                [
+                    Vec2::new(1.0 - CROSSING_INSET, 0.0),
+                    Vec2::new(1.0 - CROSSING_INSET, 1.0),
+                ],
+                [Vec2::new(0.0, 0.0), Vec2::new(CROSSING_INSET, 0.0)],
+                [Vec2::new(1.0 - CROSSING_INSET, 0.0), Vec2::new(1.0, 0.0)],
+                [Vec2::new(0.0, 1.0), Vec2::new(CROSSING_INSET, 1.0)],
+                [Vec2::new(1.0 - CROSSING_INSET, 1.0), Vec2::new(1.0, 1.0)],
+            ];
+
+            lines.into_iter().for_each(|line| {
+                let points: Vec<Vec2> = line
+                    .into_iter()
+                    .map(|point| match is_top_vertical {
+                        true => corner + point,
+                        false => corner + Vec2::new(point.y, point.x),
+                    })
+                    .collect();
+                let line_entity = commands
+                    .spawn((
+                        Mesh2d(meshes.add(get_line_mesh(&points, WALL_SIZE))),
+                        MeshMaterial2d(material.clone()),
+                        Transform::default(),
+                    ))
+                    .id();
+                commands.entity(grid_entity).add_child(line_entity);
+            });
+        });
+}
@@ -1,6 +1,11 @@
-use crate::components::*;
-use crate::resources::*;
+use super::{
+    get_grid_type_settings_display, get_maze_builder_description, get_maze_builder_settings_display,
+};
+use crate::ui::*;
 use bevy::prelude::*;
+use mazes::maze_builders::*;
+use mazes::maze_post_processors::*;
+use mazes::resources::*;
 
 const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
@@ -8,29 +13,134 @@ const HOVERED_PRESSED_BUTTON: Color = Color::srgb(0.25, 0.65, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
 const BUTTON_WIDTH: f32 = 200.0;
 const BUTTON_HEIGHT: f32 = 50.0;
+const GRID_TYPES: [GridType; 7] = [
+    GridType::Square,
+    GridType::Polar,
+    GridType::Hex,
+    GridType::Triangle,
+    GridType::Upsilon,
+    GridType::Layered,
+    GridType::Weave,
+];
+const WRAP_MODES: [WrapMode; 3] = [WrapMode::None, WrapMode::Horizontal, WrapMode::Both];
+const BIAS_PROBABILITIES: [f32; 5] = [0.1, 0.25, 0.5, 0.75, 0.9];
+const BRAID_PROBABILITIES: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+const SPARSENESSES: [Sparseness; 6] = [
+    Sparseness::Passes(0),
+    Sparseness::Passes(1),
+    Sparseness::Passes(3),
+    Sparseness::CulledFraction(0.25),
+    Sparseness::CulledFraction(0.5),
+    Sparseness::CulledFraction(0.75),
+];
+const DIAGONALS: [Diagonal; 4] = [
+    Diagonal::NorthEast,
+    Diagonal::NorthWest,
+    Diagonal::SouthEast,
+    Diagonal::SouthWest,
+];
+const RUN_ORIENTATIONS: [RunOrientation; 2] =
+    [RunOrientation::Horizontal, RunOrientation::Vertical];
+const GROWING_TREE_STRATEGIES: [GrowingTreeStrategy; 6] = [
+    GrowingTreeStrategy::Newest,
+    GrowingTreeStrategy::Random,
+    GrowingTreeStrategy::Oldest,
+    GrowingTreeStrategy::Mixed { newest_ratio: 0.75 },
+    GrowingTreeStrategy::Mixed { newest_ratio: 0.5 },
+    GrowingTreeStrategy::Mixed { newest_ratio: 0.25 },
+];
 
-fn get_maze_builder_name(maze_builder_type: MazeBuilderType) -> String {
-    match maze_builder_type {
-        MazeBuilderType::BinaryTree => String::from("Binary Tree"),
-        MazeBuilderType::Sidewinder => String::from("Sidewinder"),
-        MazeBuilderType::AldousBroder => String::from("Aldous Broder"),
-        MazeBuilderType::Wilson => String::from("Wilson"),
+fn get_probability_name(label: &str, probability: f32) -> String {
+    let percent = (probability * 100.0).round();
+    format!("{label} {percent}%")
+}
+
+fn get_grid_type_name(grid_type: GridType) -> String {
+    match grid_type {
+        GridType::Square => String::from("Square"),
+        GridType::Polar => String::from("Polar"),
+        GridType::Hex => String::from("Hex"),
+        GridType::Triangle => String::from("Triangle"),
+        GridType::Upsilon => String::from("Upsilon"),
+        GridType::Layered => String::from("3D levels"),
+        GridType::Weave => String::from("Weave"),
+    }
+}
+
+fn get_wrap_mode_name(wrap_mode: WrapMode) -> String {
+    match wrap_mode {
+        WrapMode::None => String::from("No wrap"),
+        WrapMode::Horizontal => String::from("Cylinder"),
+        WrapMode::Both => String::from("Torus"),
     }
 }
 
+fn get_sparseness_name(sparseness: Sparseness) -> String {
+    match sparseness {
+        Sparseness::Passes(0) => String::from("None"),
+        Sparseness::Passes(1) => String::from("1 pass"),
+        Sparseness::Passes(passes) => format!("{passes} passes"),
+        Sparseness::CulledFraction(fraction) => get_probability_name("Culled", fraction),
+    }
+}
+
+fn get_diagonal_name(diagonal: Diagonal) -> String {
+    match diagonal {
+        Diagonal::NorthEast => String::from("North east"),
+        Diagonal::NorthWest => String::from("North west"),
+        Diagonal::SouthEast => String::from("South east"),
+        Diagonal::SouthWest => String::from("South west"),
+    }
+}
+
+fn get_run_orientation_name(run_orientation: RunOrientation) -> String {
+    match run_orientation {
+        RunOrientation::Horizontal => String::from("Horizontal"),
+        RunOrientation::Vertical => String::from("Vertical"),
+    }
+}
+
+fn get_growing_tree_strategy_name(strategy: GrowingTreeStrategy) -> String {
+    match strategy {
+        GrowingTreeStrategy::Newest => String::from("Newest"),
+        GrowingTreeStrategy::Random => String::from("Random"),
+        GrowingTreeStrategy::Oldest => String::from("Oldest"),
+        GrowingTreeStrategy::Mixed { newest_ratio } => {
+            let newest_percent = (newest_ratio * 100.0).round();
+            let random_percent = 100.0 - newest_percent;
+            format!("Newest {newest_percent}% / Random {random_percent}%")
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn setup_hud(
     mut commands: Commands,
     overlay_state: Res<OverlayState>,
-    maze_builder_type: Res<MazeBuilderType>,
+    living_maze: Res<LivingMaze>,
+    maze_builder_registry: Res<MazeBuilderRegistry>,
+    selected_maze_builder: Res<SelectedMazeBuilder>,
+    grid_type: Res<GridType>,
+    grid_map: Res<GridMap>,
+    binary_tree_bias: Res<BinaryTreeBias>,
+    sidewinder_bias: Res<SidewinderBias>,
+    growing_tree_strategy: Res<GrowingTreeStrategy>,
+    braid: Res<Braid>,
+    sparseness: Res<Sparseness>,
 ) {
+    let mut maze_builder_dropdown = Entity::PLACEHOLDER;
     commands
         .spawn(Node {
             width: Val::Vw(100.0),
-            height: Val::Px(75.0),
+            min_height: Val::Px(75.0),
             align_items: AlignItems::Center,
             justify_content: JustifyContent::Center,
             flex_direction: FlexDirection::Row,
+            // Controls that don't fit across the window go onto another row.
+            flex_wrap: FlexWrap::Wrap,
             column_gap: Val::Px(16.0),
+            row_gap: Val::Px(8.0),
+            padding: UiRect::vertical(Val::Px(12.0)),
             ..default()
         })
         .with_children(|parent| {
@@ -46,31 +156,292 @@ pub fn setup_hud(
                 HudAction::ToggleOverlay,
                 String::from("Show solution"),
             );
+            // Origin Shift only knows how to shift a square grid map.
+            make_grid_type_settings(parent, &[GridType::Square], &grid_type, |settings| {
+                make_hud_button(
+                    settings,
+                    ButtonVariant::Toggle(living_maze.is_enabled),
+                    HudAction::ToggleLivingMaze,
+                    String::from("Living maze"),
+                );
+            });
 
+            maze_builder_dropdown = make_dropdown(
+                parent,
+                get_maze_builder_options(&maze_builder_registry),
+                selected_maze_builder.0.clone(),
+                String::from("Maze type"),
+            );
             make_dropdown(
                 parent,
-                vec![
-                    (
-                        get_maze_builder_name(MazeBuilderType::BinaryTree),
-                        HudAction::ChangeMazeType(MazeBuilderType::BinaryTree),
-                    ),
-                    (
-                        get_maze_builder_name(MazeBuilderType::Sidewinder),
-                        HudAction::ChangeMazeType(MazeBuilderType::Sidewinder),
-                    ),
-                    (
-                        get_maze_builder_name(MazeBuilderType::AldousBroder),
-                        HudAction::ChangeMazeType(MazeBuilderType::AldousBroder),
-                    ),
-                    (
-                        get_maze_builder_name(MazeBuilderType::Wilson),
-                        HudAction::ChangeMazeType(MazeBuilderType::Wilson),
-                    ),
+                GRID_TYPES
+                    .into_iter()
+                    .map(|grid_type| {
+                        (
+                            get_grid_type_name(grid_type),
+                            HudAction::ChangeGridType(grid_type),
+                        )
+                    })
+                    .collect(),
+                get_grid_type_name(*grid_type),
+                String::from("Grid"),
+            );
+            make_grid_type_settings(parent, &[GridType::Square], &grid_type, |settings| {
+                make_dropdown(
+                    settings,
+                    WRAP_MODES
+                        .into_iter()
+                        .map(|wrap_mode| {
+                            (
+                                get_wrap_mode_name(wrap_mode),
+                                HudAction::ChangeWrapMode(wrap_mode),
+                            )
+                        })
+                        .collect(),
+                    get_wrap_mode_name(grid_map.get_wrap_mode()),
+                    String::from("Wrap"),
+                );
+            });
+            make_grid_type_settings(parent, &[GridType::Layered], &grid_type, |settings| {
+                make_hud_button(
+                    settings,
+                    ButtonVariant::Normal,
+                    HudAction::LevelDown,
+                    String::from("Level down"),
+                );
+                make_hud_button(
+                    settings,
+                    ButtonVariant::Normal,
+                    HudAction::LevelUp,
+                    String::from("Level up"),
+                );
+            });
+            // Braiding and sparseness aren't used for weave mazes.
+            make_grid_type_settings(
+                parent,
+                &[
+                    GridType::Square,
+                    GridType::Polar,
+                    GridType::Hex,
+                    GridType::Triangle,
+                    GridType::Upsilon,
+                    GridType::Layered,
                 ],
-                get_maze_builder_name(*maze_builder_type),
-                String::from("Maze type"),
+                &grid_type,
+                |settings| {
+                    make_dropdown(
+                        settings,
+                        BRAID_PROBABILITIES
+                            .into_iter()
+                            .map(|probability| {
+                                (
+                                    get_probability_name("Braid", probability),
+                                    HudAction::ChangeBraidProbability(probability),
+                                )
+                            })
+                            .collect(),
+                        get_probability_name("Braid", braid.dead_end_probability),
+                        String::from("Braid %"),
+                    );
+                    make_dropdown(
+                        settings,
+                        SPARSENESSES
+                            .into_iter()
+                            .map(|sparseness| {
+                                (
+                                    get_sparseness_name(sparseness),
+                                    HudAction::ChangeSparseness(sparseness),
+                                )
+                            })
+                            .collect(),
+                        get_sparseness_name(*sparseness),
+                        String::from("Sparseness"),
+                    );
+                },
             );
+
+            make_maze_builder_settings(
+                parent,
+                BinaryTree::default().name(),
+                &selected_maze_builder,
+                |settings| {
+                    make_dropdown(
+                        settings,
+                        DIAGONALS
+                            .into_iter()
+                            .map(|diagonal| {
+                                (
+                                    get_diagonal_name(diagonal),
+                                    HudAction::ChangeBinaryTreeDiagonal(diagonal),
+                                )
+                            })
+                            .collect(),
+                        get_diagonal_name(binary_tree_bias.diagonal),
+                        String::from("Diagonal"),
+                    );
+                    make_dropdown(
+                        settings,
+                        BIAS_PROBABILITIES
+                            .into_iter()
+                            .map(|probability| {
+                                (
+                                    get_probability_name("Vertical", probability),
+                                    HudAction::ChangeBinaryTreeProbability(probability),
+                                )
+                            })
+                            .collect(),
+                        get_probability_name("Vertical", binary_tree_bias.vertical_probability),
+                        String::from("Vertical %"),
+                    );
+                },
+            );
+
+            make_maze_builder_settings(
+                parent,
+                Sidewinder::default().name(),
+                &selected_maze_builder,
+                |settings| {
+                    make_dropdown(
+                        settings,
+                        RUN_ORIENTATIONS
+                            .into_iter()
+                            .map(|run_orientation| {
+                                (
+                                    get_run_orientation_name(run_orientation),
+                                    HudAction::ChangeSidewinderRunOrientation(run_orientation),
+                                )
+                            })
+                            .collect(),
+                        get_run_orientation_name(sidewinder_bias.run_orientation),
+                        String::from("Runs"),
+                    );
+                    make_dropdown(
+                        settings,
+                        BIAS_PROBABILITIES
+                            .into_iter()
+                            .map(|probability| {
+                                (
+                                    get_probability_name("Run", probability),
+                                    HudAction::ChangeSidewinderProbability(probability),
+                                )
+                            })
+                            .collect(),
+                        get_probability_name("Run", sidewinder_bias.run_probability),
+                        String::from("Run %"),
+                    );
+                },
+            );
+
+            make_maze_builder_settings(
+                parent,
+                GrowingTree::default().name(),
+                &selected_maze_builder,
+                |settings| {
+                    make_dropdown(
+                        settings,
+                        GROWING_TREE_STRATEGIES
+                            .into_iter()
+                            .map(|strategy| {
+                                (
+                                    get_growing_tree_strategy_name(strategy),
+                                    HudAction::ChangeGrowingTreeStrategy(strategy),
+                                )
+                            })
+                            .collect(),
+                        get_growing_tree_strategy_name(*growing_tree_strategy),
+                        String::from("Growing tree"),
+                    );
+                },
+            );
+
+            // Takes up a whole row, so it's always below the other controls.
+            parent.spawn((
+                MazeBuilderDescription,
+                Text::new(get_maze_builder_description(
+                    &maze_builder_registry,
+                    &selected_maze_builder,
+                )),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextLayout::new_with_justify(JustifyText::Center),
+                Node {
+                    width: Val::Percent(100.0),
+                    ..default()
+                },
+            ));
         });
+
+    // Builders can be registered after startup, so this dropdown's options are
+    // rebuilt by `update_maze_builder_dropdown`.
+    commands
+        .entity(maze_builder_dropdown)
+        .insert(MazeBuilderDropdown);
+}
+
+pub fn get_maze_builder_options(
+    maze_builder_registry: &MazeBuilderRegistry,
+) -> Vec<(String, HudAction)> {
+    maze_builder_registry
+        .iter()
+        .map(|maze_builder| {
+            (
+                String::from(maze_builder.name()),
+                HudAction::ChangeMazeBuilder(String::from(maze_builder.name())),
+            )
+        })
+        .collect()
+}
+
+// Spawns a container for a maze builder's settings, which is only displayed
+// while that builder is selected.
+fn make_maze_builder_settings(
+    builder: &mut ChildBuilder,
+    maze_builder_name: &str,
+    selected_maze_builder: &SelectedMazeBuilder,
+    spawn_settings: impl FnOnce(&mut ChildBuilder),
+) {
+    let maze_builder_settings = MazeBuilderSettings(String::from(maze_builder_name));
+    let display = get_maze_builder_settings_display(&maze_builder_settings, selected_maze_builder);
+
+    builder
+        .spawn((
+            maze_builder_settings,
+            Node {
+                display,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(16.0),
+                ..default()
+            },
+        ))
+        .with_children(spawn_settings);
+}
+
+// Spawns a container for controls that only apply to some grid types, which is
+// only displayed while one of those grid types is selected.
+fn make_grid_type_settings(
+    builder: &mut ChildBuilder,
+    grid_types: &[GridType],
+    grid_type: &GridType,
+    spawn_settings: impl FnOnce(&mut ChildBuilder),
+) {
+    let grid_type_settings = GridTypeSettings(grid_types.to_vec());
+    let display = get_grid_type_settings_display(&grid_type_settings, grid_type);
+
+    builder
+        .spawn((
+            grid_type_settings,
+            Node {
+                display,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(16.0),
+                ..default()
+            },
+        ))
+        .with_children(spawn_settings);
 }
 
 pub fn make_hud_button(
@@ -111,7 +482,7 @@ fn make_dropdown(
     options: Vec<(String, HudAction)>,
     active_option: String,
     label: String,
-) {
+) -> Entity {
     // Parent everything within a 'Dropdown' node.
     builder
         .spawn((
@@ -135,8 +506,6 @@ fn make_dropdown(
                 label,
             );
 
-            let option_count = options.len() as f32;
-
             // And a 'DropdownMenu', which is hidden by default.
             dropdown
                 .spawn((
@@ -145,7 +514,7 @@ fn make_dropdown(
                     Node {
                         top: Val::Px(BUTTON_HEIGHT + 10.0),
                         width: Val::Px(BUTTON_WIDTH),
-                        height: Val::Px(option_count * BUTTON_HEIGHT),
+                        height: get_dropdown_menu_height(options.len()),
                         align_items: AlignItems::Center,
                         justify_content: JustifyContent::Center,
                         flex_direction: FlexDirection::Column,
@@ -155,18 +524,31 @@ fn make_dropdown(
                     Visibility::Hidden,
                 ))
                 .with_children(|dropdown_menu| {
-                    // The 'DropdownMenu' contains each option.
-                    options.into_iter().for_each(|(option_text, action)| {
-                        let is_active = active_option == option_text;
-                        make_hud_button(
-                            dropdown_menu,
-                            ButtonVariant::Radio(is_active),
-                            action,
-                            option_text,
-                        );
-                    });
+                    make_dropdown_options(dropdown_menu, options, &active_option);
                 });
-        });
+        })
+        .id()
+}
+
+pub fn get_dropdown_menu_height(option_count: usize) -> Val {
+    Val::Px(option_count as f32 * BUTTON_HEIGHT)
+}
+
+// The 'DropdownMenu' contains each option.
+pub fn make_dropdown_options(
+    dropdown_menu: &mut ChildBuilder,
+    options: Vec<(String, HudAction)>,
+    active_option: &str,
+) {
+    options.into_iter().for_each(|(option_text, action)| {
+        let is_active = active_option == option_text;
+        make_hud_button(
+            dropdown_menu,
+            ButtonVariant::Radio(is_active),
+            action,
+            option_text,
+        );
+    });
 }
 
 // This system handles changing all buttons color based on mouse interaction.
@@ -228,6 +610,7 @@ pub fn update_button_colors(
 }
 
 // This system handles changing all buttons color based on mouse interaction
+#[allow(clippy::type_complexity)]
 pub fn update_toggle_button_state(
     mut interaction_query: Query<
         (&Interaction, &mut ButtonVariant),
@@ -245,6 +628,7 @@ pub fn update_toggle_button_state(
 }
 
 // This system handles opening and closing dropdown menus.
+#[allow(clippy::type_complexity)]
 pub fn update_dropdown_menu_open_state(
     mut interaction_query: Query<
         (&Interaction, &mut ButtonVariant, &Parent),
@@ -288,7 +672,7 @@ pub fn update_radio_state(
         });
 
     // If there isn't one, return early, there's nothing to do.
-    if None == pressed_radio_button {
+    if pressed_radio_button.is_none() {
         return;
     }
 
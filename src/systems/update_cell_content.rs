@@ -1,30 +1,50 @@
 use super::map_utils::*;
-use crate::components::*;
-use crate::resources::*;
+use super::Grids;
+use crate::ui::*;
 use bevy::prelude::*;
+use mazes::resources::*;
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn update_cell_content(
+    grid_type: Res<GridType>,
+    grids: Grids,
     solution: Res<Solution>,
+    inaccessible_cells: Res<InaccessibleCells>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     cell_query: Query<(&Cell, &Children)>,
     mut cell_content_text_query: Query<
         (&mut Text2d, &mut TextColor),
         (With<CellContentText>, Without<CellOverlayText>),
     >,
+    mut cell_content_background_query: Query<
+        &mut MeshMaterial2d<ColorMaterial>,
+        (With<CellContentBackground>, Without<CellOverlayBackground>),
+    >,
     mut cell_overlay_background_query: Query<
         &mut MeshMaterial2d<ColorMaterial>,
-        With<CellOverlayBackground>,
+        (With<CellOverlayBackground>, Without<CellContentBackground>),
     >,
     mut cell_overlay_text_query: Query<
         (&mut Text2d, &mut TextColor),
         (With<CellOverlayText>, Without<CellContentText>),
     >,
 ) {
+    let grid = grids.get_drawn_grid(*grid_type);
     for (cell, children) in &cell_query {
-        let distance = solution.distances.get(&cell.position);
+        let distance = solution.distances.get(grid, cell.position);
         let is_start = cell.position == solution.start;
         let is_end = cell.position == solution.end;
         let is_on_path = solution.path.contains_key(&cell.position);
+        let is_inaccessible = inaccessible_cells.0.contains(&cell.position);
+
+        // Cells can be filled in or opened up again whenever the maze changes.
+        let mut content_background_material =
+            cell_content_background_query.get_mut(children[0]).unwrap();
+        let old_material_id = content_background_material.id();
+        let content_background_color =
+            get_cell_background_color(distance, solution.farthest_distance, false, is_inaccessible);
+        content_background_material.0 = materials.add(content_background_color);
+        materials.remove(old_material_id);
 
         let (mut content_text, mut content_text_color) =
             cell_content_text_query.get_mut(children[1]).unwrap();
@@ -35,7 +55,7 @@ pub fn update_cell_content(
             cell_overlay_background_query.get_mut(children[2]).unwrap();
         let old_material_id = overlay_background_material.id();
         let overlay_background_color =
-            get_cell_background_color(distance, solution.farthest_distance, true);
+            get_cell_background_color(distance, solution.farthest_distance, true, is_inaccessible);
         overlay_background_material.0 = materials.add(overlay_background_color);
         materials.remove(old_material_id);
 
@@ -0,0 +1,23 @@
+use crate::ui::*;
+use bevy::prelude::*;
+use mazes::resources::*;
+
+pub fn update_grid_type_settings_visibility(
+    grid_type: Res<GridType>,
+    mut grid_type_settings_query: Query<(&GridTypeSettings, &mut Node)>,
+) {
+    for (grid_type_settings, mut node) in &mut grid_type_settings_query {
+        node.display = get_grid_type_settings_display(grid_type_settings, &grid_type);
+    }
+}
+
+pub fn get_grid_type_settings_display(
+    grid_type_settings: &GridTypeSettings,
+    grid_type: &GridType,
+) -> Display {
+    if grid_type_settings.0.contains(grid_type) {
+        Display::Flex
+    } else {
+        Display::None
+    }
+}
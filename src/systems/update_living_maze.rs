@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use fastrand::Rng;
+use mazes::maze_builders::OriginShiftMaze;
+use mazes::resources::*;
+use mazes::utils::solve_maze;
+
+// Shifts the origin of the current maze every few frames, so that the maze
+// slowly changes while always staying a perfect maze, apart from any loops that
+// braiding added.
+#[allow(clippy::too_many_arguments)]
+pub fn update_living_maze(
+    grid_map: Res<GridMap>,
+    living_maze: Res<LivingMaze>,
+    rng_seed: Res<RngSeed>,
+    inaccessible_cells: Res<InaccessibleCells>,
+    mut removed_walls: ResMut<RemovedWalls>,
+    mut solution: ResMut<Solution>,
+    mut frame_count: Local<u32>,
+    mut maze: Local<Option<OriginShiftMaze>>,
+    mut loop_walls: Local<WallSet>,
+    mut rng: Local<Rng>,
+) {
+    // The tree is only built again when the maze has been regenerated, so each
+    // shift just moves the origin. This system's own shifts don't count as
+    // changes, and changes are only seen by the next run, so this is checked on
+    // every frame. The origin carries over unless the new maze has filled it in
+    // or it's no longer on the grid map, in which case the tree couldn't reach any
+    // other cell from it.
+    if maze.is_none() || removed_walls.is_changed() || inaccessible_cells.is_changed() {
+        let origin_pos = maze
+            .as_ref()
+            .map(|maze| maze.get_origin())
+            .filter(|origin_pos| {
+                grid_map.is_cell_enabled(*origin_pos) && !inaccessible_cells.0.contains(origin_pos)
+            })
+            .unwrap_or(solution.end);
+        let tree = OriginShiftMaze::from_removed_walls(&grid_map, &removed_walls.0, origin_pos);
+
+        // The tree only keeps one route between cells, so the walls braiding
+        // removed to make loops aren't part of it. They're kept open while the
+        // tree shifts around them, rather than being closed the first time the
+        // tree gives up a passage through one of them.
+        let tree_walls = tree.get_removed_walls(&grid_map);
+        *loop_walls = grid_map.new_link_set();
+        loop_walls.extend(
+            removed_walls
+                .0
+                .iter()
+                .filter(|wall| !tree_walls.contains(wall)),
+        );
+        *maze = Some(tree);
+        // The same maze always changes in the same way.
+        *rng = Rng::with_seed(rng_seed.0);
+    }
+
+    *frame_count += 1;
+    if *frame_count < living_maze.frames_per_shift {
+        return;
+    }
+    *frame_count = 0;
+
+    let Some(maze) = maze.as_mut() else {
+        return;
+    };
+    if let Some((removed_wall, restored_wall)) = maze.shift(&grid_map, &mut rng) {
+        if !loop_walls.contains(&restored_wall) {
+            removed_walls.0.remove(&restored_wall);
+        }
+        removed_walls.0.insert(removed_wall);
+        *solution = solve_maze(&*grid_map, &removed_walls.0, &inaccessible_cells.0);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::ui::*;
+use bevy::prelude::*;
+use mazes::resources::*;
+
+pub fn update_maze_builder_description(
+    maze_builder_registry: Res<MazeBuilderRegistry>,
+    selected_maze_builder: Res<SelectedMazeBuilder>,
+    mut description_query: Query<&mut Text, With<MazeBuilderDescription>>,
+) {
+    for mut text in &mut description_query {
+        text.0 = get_maze_builder_description(&maze_builder_registry, &selected_maze_builder);
+    }
+}
+
+// Empty when the selected builder hasn't been registered.
+pub fn get_maze_builder_description(
+    maze_builder_registry: &MazeBuilderRegistry,
+    selected_maze_builder: &SelectedMazeBuilder,
+) -> String {
+    maze_builder_registry
+        .get(&selected_maze_builder.0)
+        .map_or_else(String::new, |maze_builder| {
+            String::from(maze_builder.description())
+        })
+}
@@ -0,0 +1,30 @@
+use super::{get_dropdown_menu_height, get_maze_builder_options, make_dropdown_options};
+use crate::ui::*;
+use bevy::prelude::*;
+use mazes::resources::*;
+
+// Lists the builders that are registered now, including any registered after
+// the HUD was set up.
+pub fn update_maze_builder_dropdown(
+    mut commands: Commands,
+    maze_builder_registry: Res<MazeBuilderRegistry>,
+    selected_maze_builder: Res<SelectedMazeBuilder>,
+    dropdown_children_query: Query<&Children, With<MazeBuilderDropdown>>,
+    mut dropdown_menu_query: Query<&mut Node, With<DropdownMenu>>,
+) {
+    for dropdown_children in &dropdown_children_query {
+        let dropdown_menu = dropdown_children[1];
+        let Ok(mut dropdown_menu_node) = dropdown_menu_query.get_mut(dropdown_menu) else {
+            continue;
+        };
+
+        let options = get_maze_builder_options(&maze_builder_registry);
+        dropdown_menu_node.height = get_dropdown_menu_height(options.len());
+        commands
+            .entity(dropdown_menu)
+            .despawn_descendants()
+            .with_children(|dropdown_menu| {
+                make_dropdown_options(dropdown_menu, options, &selected_maze_builder.0);
+            });
+    }
+}
@@ -0,0 +1,32 @@
+use bevy::prelude::*;
+use mazes::maze_builders::*;
+use mazes::resources::*;
+
+// Builders with settings are re-registered when their settings change, which
+// also triggers the maze to be regenerated.
+pub fn update_binary_tree_builder(
+    binary_tree_bias: Res<BinaryTreeBias>,
+    mut maze_builder_registry: ResMut<MazeBuilderRegistry>,
+) {
+    maze_builder_registry.register(BinaryTree {
+        bias: *binary_tree_bias,
+    });
+}
+
+pub fn update_sidewinder_builder(
+    sidewinder_bias: Res<SidewinderBias>,
+    mut maze_builder_registry: ResMut<MazeBuilderRegistry>,
+) {
+    maze_builder_registry.register(Sidewinder {
+        bias: *sidewinder_bias,
+    });
+}
+
+pub fn update_growing_tree_builder(
+    growing_tree_strategy: Res<GrowingTreeStrategy>,
+    mut maze_builder_registry: ResMut<MazeBuilderRegistry>,
+) {
+    maze_builder_registry.register(GrowingTree {
+        strategy: *growing_tree_strategy,
+    });
+}
@@ -0,0 +1,24 @@
+use crate::ui::*;
+use bevy::prelude::*;
+use mazes::resources::*;
+
+pub fn update_maze_builder_settings_visibility(
+    selected_maze_builder: Res<SelectedMazeBuilder>,
+    mut maze_builder_settings_query: Query<(&MazeBuilderSettings, &mut Node)>,
+) {
+    for (maze_builder_settings, mut node) in &mut maze_builder_settings_query {
+        node.display =
+            get_maze_builder_settings_display(maze_builder_settings, &selected_maze_builder);
+    }
+}
+
+pub fn get_maze_builder_settings_display(
+    maze_builder_settings: &MazeBuilderSettings,
+    selected_maze_builder: &SelectedMazeBuilder,
+) -> Display {
+    if maze_builder_settings.0 == selected_maze_builder.0 {
+        Display::Flex
+    } else {
+        Display::None
+    }
+}
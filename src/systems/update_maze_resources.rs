@@ -1,47 +1,193 @@
-use crate::maze_builders::*;
-use crate::resources::*;
-use crate::utils::*;
-use bevy::prelude::*;
+use super::Grids;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use fastrand::Rng;
+use mazes::components::Wall;
+use mazes::maze_builders::MazeBuilder;
+use mazes::maze_post_processors::*;
+use mazes::resources::*;
+use mazes::utils::*;
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_maze_resources(
-    grid_map: Res<GridMap>,
+    grid_type: Res<GridType>,
+    grids: Grids,
+    mut layered_maze: ResMut<LayeredMaze>,
+    mut visible_level: ResMut<VisibleLevel>,
+    mut weave_maze: ResMut<WeaveMaze>,
     rng_seed: Res<RngSeed>,
-    maze_builder_type: Res<MazeBuilderType>,
+    maze_builder_registry: Res<MazeBuilderRegistry>,
+    selected_maze_builder: Res<SelectedMazeBuilder>,
+    braid: Res<Braid>,
+    sparseness: Res<Sparseness>,
     mut removed_walls: ResMut<RemovedWalls>,
+    mut inaccessible_cells: ResMut<InaccessibleCells>,
     mut solution: ResMut<Solution>,
 ) {
-    removed_walls.0 = match *maze_builder_type {
-        MazeBuilderType::AldousBroder => carve_aldous_broder_into_grid_map(&grid_map, rng_seed.0),
-        MazeBuilderType::Wilson => carve_wilson_into_grid_map(&grid_map, rng_seed.0),
-        MazeBuilderType::BinaryTree => carve_binary_tree_into_grid_map(&grid_map, rng_seed.0),
-        MazeBuilderType::Sidewinder => carve_sidewinder_into_grid_map(&grid_map, rng_seed.0),
+    let Some(maze_builder) = maze_builder_registry.get(&selected_maze_builder.0) else {
+        // The selected builder hasn't been registered, so there's nothing to carve.
+        return;
     };
 
-    // The following process determines a start cell and end cell that are
-    // the furthest apart on the map and finds the quickest path between those points.
+    let mut rng = Rng::with_seed(rng_seed.0);
+    let (grid, carved_walls): (&dyn Grid<Cell = IVec2, Link = Wall, LinkSet = WallSet>, _) =
+        match *grid_type {
+            GridType::Square => (
+                &*grids.grid_map,
+                Some(maze_builder.carve(&grids.grid_map, &mut rng)),
+            ),
+            GridType::Polar => (
+                &*grids.polar_grid,
+                maze_builder.carve_grid(&*grids.polar_grid, &mut rng),
+            ),
+            GridType::Hex => (
+                &*grids.hex_grid,
+                maze_builder.carve_grid(&*grids.hex_grid, &mut rng),
+            ),
+            GridType::Triangle => (
+                &*grids.triangle_grid,
+                maze_builder.carve_grid(&*grids.triangle_grid, &mut rng),
+            ),
+            GridType::Upsilon => (
+                &*grids.upsilon_grid,
+                maze_builder.carve_grid(&*grids.upsilon_grid, &mut rng),
+            ),
+            GridType::Layered => {
+                let layered_grid = &*grids.layered_grid;
+                let mut removed_links = maze_builder
+                    .carve_layered_grid(layered_grid, &mut rng)
+                    .unwrap_or_else(|| {
+                        warn_cant_carve(maze_builder, *grid_type);
+                        HashSet::new()
+                    });
+                braid_removed_walls(layered_grid, &mut rng, &mut removed_links, *braid);
+                let culled_cells =
+                    cull_dead_ends(layered_grid, &mut rng, &mut removed_links, *sparseness);
+                layered_maze.solution = solve_maze(layered_grid, &removed_links, &culled_cells);
+                layered_maze.removed_links = removed_links;
+                layered_maze.inaccessible_cells = culled_cells;
 
-    // First pick a temporary start position in the corner of the map.
-    let start = grid_map.get_north_east_cell_pos();
+                // Show the level the solution starts on. This also redraws the grid, as
+                // the stairs have moved.
+                visible_level.0 = layered_maze.solution.start.z;
+                (*removed_walls, *inaccessible_cells, *solution) =
+                    get_level_view(layered_grid, &layered_maze, visible_level.0);
+                return;
+            }
+            GridType::Weave => {
+                let weave_grid = &*grids.weave_grid;
+                // Braiding and sparseness aren't used, they'd have to keep the cells
+                // that are tunnelled under as straight corridors.
+                let removed_links = maze_builder
+                    .carve_weave_grid(weave_grid, &mut rng)
+                    .unwrap_or_else(|| {
+                        warn_cant_carve(maze_builder, *grid_type);
+                        HashSet::new()
+                    });
+                weave_maze.solution = solve_maze(weave_grid, &removed_links, &HashSet::new());
+                weave_maze.removed_links = removed_links;
 
-    // Get the distances from that start and find furthest cell, which will be the actual end.
-    let distances = dijkstra(start, &grid_map, &removed_walls.0);
-    let (end, _) = get_most_distant(&distances);
+                (*removed_walls, *inaccessible_cells, *solution) =
+                    get_weave_view(weave_grid, &weave_maze);
+                return;
+            }
+        };
+    removed_walls.0 = carved_walls.unwrap_or_else(|| {
+        warn_cant_carve(maze_builder, *grid_type);
+        grid.new_link_set()
+    });
+    // Braiding first means a fully braided maze has no dead ends left to cull.
+    braid_removed_walls(grid, &mut rng, &mut removed_walls.0, *braid);
+    inaccessible_cells.0 = cull_dead_ends(grid, &mut rng, &mut removed_walls.0, *sparseness);
+    *solution = solve_maze(grid, &removed_walls.0, &inaccessible_cells.0);
+}
 
-    // Repeat this process from the end to get the actual start.
-    let distances = dijkstra(end, &grid_map, &removed_walls.0);
-    let (start, farthest_distance) = get_most_distant(&distances);
+fn warn_cant_carve(maze_builder: &dyn MazeBuilder, grid_type: GridType) {
+    warn!(
+        "The {} builder can't carve a {:?} grid",
+        maze_builder.name(),
+        grid_type
+    );
+}
 
-    // Finally get the distances from the new start.
-    let distances = dijkstra(start, &grid_map, &removed_walls.0);
+// Copies one level of the layered maze into the resources that are drawn. The
+// start or end of the solution might be on another level, in which case it's
+// moved off the grid map so that no cell is marked with it.
+pub fn get_level_view(
+    layered_grid: &LayeredGrid,
+    layered_maze: &LayeredMaze,
+    level: i32,
+) -> (RemovedWalls, InaccessibleCells, Solution) {
+    let get_level_cells = |cells: &HashMap<IVec3, i32>| -> HashMap<IVec2, i32> {
+        cells
+            .iter()
+            .filter(|(cell, _)| cell.z == level)
+            .map(|(cell, distance)| (cell.xy(), *distance))
+            .collect()
+    };
+    let get_level_cell = |cell: IVec3| match cell.z == level {
+        true => cell.xy(),
+        false => IVec2::NEG_ONE,
+    };
 
-    // Store the path from the start to the end.
-    let path = get_path(start, end, &distances, &grid_map, &removed_walls.0);
+    let solution = &layered_maze.solution;
+    let level_distances = solution
+        .distances
+        .iter()
+        .filter(|(cell, _)| cell.z == level)
+        .map(|(cell, distance)| (cell.xy(), distance));
+    (
+        RemovedWalls(layered_grid.get_level_walls(&layered_maze.removed_links, level)),
+        InaccessibleCells(
+            layered_maze
+                .inaccessible_cells
+                .iter()
+                .filter(|cell| cell.z == level)
+                .map(|cell| cell.xy())
+                .collect(),
+        ),
+        Solution {
+            start: get_level_cell(solution.start),
+            end: get_level_cell(solution.end),
+            path: get_level_cells(&solution.path),
+            distances: Distances::from_cells(layered_grid.get_grid_map(), level_distances),
+            // Kept from the whole maze so the colours match between levels.
+            farthest_distance: solution.farthest_distance,
+        },
+    )
+}
 
-    *solution = Solution {
-        start,
-        end,
-        path,
-        distances,
-        farthest_distance,
+// Copies the weave maze into the resources that are drawn. The tunnels aren't
+// drawn as cells, so only the cells on top are shown.
+pub fn get_weave_view(
+    weave_grid: &WeaveGrid,
+    weave_maze: &WeaveMaze,
+) -> (RemovedWalls, InaccessibleCells, Solution) {
+    let get_top_cells = |cells: &HashMap<IVec3, i32>| -> HashMap<IVec2, i32> {
+        cells
+            .iter()
+            .filter(|(cell, _)| cell.z == 0)
+            .map(|(cell, distance)| (cell.xy(), *distance))
+            .collect()
     };
+
+    let solution = &weave_maze.solution;
+    let top_distances = solution
+        .distances
+        .iter()
+        .filter(|(cell, _)| cell.z == 0)
+        .map(|(cell, distance)| (cell.xy(), distance));
+    (
+        RemovedWalls(weave_grid.get_removed_walls(&weave_maze.removed_links)),
+        InaccessibleCells::default(),
+        Solution {
+            start: solution.start.xy(),
+            end: solution.end.xy(),
+            path: get_top_cells(&solution.path),
+            distances: Distances::from_cells(weave_grid.get_grid_map(), top_distances),
+            farthest_distance: solution.farthest_distance,
+        },
+    )
 }
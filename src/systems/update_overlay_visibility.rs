@@ -1,5 +1,4 @@
-use crate::components::*;
-use crate::resources::*;
+use crate::ui::*;
 use bevy::prelude::*;
 
 pub fn update_overlay_visibility(
@@ -0,0 +1,16 @@
+use super::get_level_view;
+use bevy::prelude::*;
+use mazes::resources::*;
+
+// Shows a different level of the layered maze, once the HUD has changed it.
+pub fn update_visible_level(
+    layered_grid: Res<LayeredGrid>,
+    layered_maze: Res<LayeredMaze>,
+    visible_level: Res<VisibleLevel>,
+    mut removed_walls: ResMut<RemovedWalls>,
+    mut inaccessible_cells: ResMut<InaccessibleCells>,
+    mut solution: ResMut<Solution>,
+) {
+    (*removed_walls, *inaccessible_cells, *solution) =
+        get_level_view(&layered_grid, &layered_maze, visible_level.0);
+}
@@ -1,10 +1,12 @@
-use crate::components::*;
-use crate::resources::*;
+use crate::ui::*;
 use bevy::prelude::*;
+use mazes::components::*;
+use mazes::resources::*;
 
 pub fn update_wall_visibility(
     removed_walls: Res<RemovedWalls>,
     mut walls_query: Query<(&Wall, &mut Visibility)>,
+    mut seam_markers_query: Query<(&SeamMarker, &mut Visibility), Without<Wall>>,
 ) {
     for (wall, mut visibility) in &mut walls_query {
         *visibility = if removed_walls.0.contains(wall) {
@@ -13,4 +15,12 @@ pub fn update_wall_visibility(
             Visibility::Visible
         };
     }
+
+    for (SeamMarker(wall), mut visibility) in &mut seam_markers_query {
+        *visibility = if removed_walls.0.contains(wall) {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
 }
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use mazes::components::Wall;
+use mazes::maze_builders::{Diagonal, GrowingTreeStrategy, RunOrientation};
+use mazes::maze_post_processors::Sparseness;
+use mazes::resources::{GridType, WrapMode};
+
+#[derive(Resource)]
+pub struct OverlayState(pub bool);
+
+#[derive(Component)]
+pub enum HudAction {
+    ToggleOverlay,
+    ToggleLivingMaze,
+    RandomiseSeed,
+    ChangeMazeBuilder(String),
+    ChangeGridType(GridType),
+    ChangeWrapMode(WrapMode),
+    LevelDown,
+    LevelUp,
+    ChangeBinaryTreeDiagonal(Diagonal),
+    ChangeBinaryTreeProbability(f32),
+    ChangeSidewinderRunOrientation(RunOrientation),
+    ChangeSidewinderProbability(f32),
+    ChangeGrowingTreeStrategy(GrowingTreeStrategy),
+    ChangeBraidProbability(f32),
+    ChangeSparseness(Sparseness),
+    None,
+}
+
+#[derive(Component, Eq, PartialEq, Copy, Clone)]
+pub enum ButtonVariant {
+    Normal,
+    // bool = is_toggle_active
+    Toggle(bool),
+    // bool = is_dropdown_open
+    DropdownOpener(bool),
+    // bool = is_option_active
+    Radio(bool),
+}
+
+#[derive(Component, Eq, PartialEq, Copy, Clone)]
+pub struct Dropdown;
+
+#[derive(Component, Eq, PartialEq, Copy, Clone)]
+pub struct DropdownMenu;
+
+#[derive(Component, Eq, PartialEq, Copy, Clone)]
+pub struct RadioGroup;
+
+// The dropdown for picking a maze builder, its options are rebuilt whenever the
+// `MazeBuilderRegistry` changes.
+#[derive(Component)]
+pub struct MazeBuilderDropdown;
+
+// The text under the HUD that describes the selected maze builder.
+#[derive(Component)]
+pub struct MazeBuilderDescription;
+
+// HUD controls for a maze builder's settings, only shown when the builder with
+// this name is selected.
+#[derive(Component)]
+pub struct MazeBuilderSettings(pub String);
+
+// HUD controls that only apply to some grid types, only shown when one of those
+// grid types is selected.
+#[derive(Component)]
+pub struct GridTypeSettings(pub Vec<GridType>);
+
+// The entity that every part of the drawn grid is a child of, so the whole grid
+// can be despawned and drawn again when the grid type or grid map changes.
+#[derive(Component)]
+pub struct GridMapRoot;
+
+// One of the pair of markers either side of a seam in a grid map that wraps
+// around, shown when the seam's wall has been removed.
+#[derive(Component)]
+pub struct SeamMarker(pub Wall);
+
+#[derive(Component, Hash, Eq, PartialEq, Copy, Clone)]
+pub struct Cell {
+    pub position: IVec2,
+}
+
+#[derive(Component)]
+pub struct CellContentText;
+#[derive(Component)]
+pub struct CellContentBackground;
+
+#[derive(Component)]
+pub struct CellOverlayText;
+#[derive(Component)]
+pub struct CellOverlayBackground;
+
+#[derive(Component)]
+pub struct OverlayVisibility(pub bool);
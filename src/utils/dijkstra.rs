@@ -1,124 +1,271 @@
-use crate::components::Wall;
-use crate::resources::GridMap;
-use bevy::{
-    prelude::*,
-    utils::{HashMap, HashSet},
-};
-
-fn get_traversable_neighbours(
-    from: IVec2,
-    grid_map: &GridMap,
-    removed_walls: &HashSet<Wall>,
-) -> Vec<IVec2> {
-    let neighbours = vec![
-        (
-            Dir2::NORTH,
-            grid_map.neighbour_from_cell_pos(from, Dir2::NORTH),
-        ),
-        (
-            Dir2::EAST,
-            grid_map.neighbour_from_cell_pos(from, Dir2::EAST),
-        ),
-        (
-            Dir2::SOUTH,
-            grid_map.neighbour_from_cell_pos(from, Dir2::SOUTH),
-        ),
-        (
-            Dir2::WEST,
-            grid_map.neighbour_from_cell_pos(from, Dir2::WEST),
-        ),
-    ];
-
-    return neighbours
-        .into_iter()
-        .filter(|(dir, _)| {
-            let wall = grid_map.inner_wall_from_cell_pos(from, *dir);
-            if let Some(wall) = wall {
-                return removed_walls.contains(&wall);
+use crate::resources::{Grid, Solution};
+use bevy::utils::{HashMap, HashSet};
+
+// The distance from the cell `dijkstra` started at to every cell it could reach.
+// Distances are kept by cell index, so looking one up needs the grid.
+pub struct Distances<C> {
+    // -1 for cells that couldn't be reached.
+    by_index: Vec<i32>,
+    // The cells that could be reached and their distances, nearest first.
+    reached: Vec<(C, i32)>,
+}
+
+impl<C> Default for Distances<C> {
+    fn default() -> Self {
+        Distances {
+            by_index: vec![],
+            reached: vec![],
+        }
+    }
+}
+
+impl<C: Copy> Distances<C> {
+    // Distances that were worked out on another grid, like those of the cells on
+    // one level of a `LayeredGrid`, for the same cells on `grid`. Cells that
+    // aren't on `grid` are left out.
+    pub fn from_cells<G: Grid<Cell = C> + ?Sized>(
+        grid: &G,
+        cells: impl IntoIterator<Item = (C, i32)>,
+    ) -> Self {
+        let mut distances = Distances {
+            by_index: vec![-1; grid.cell_index_count()],
+            reached: vec![],
+        };
+        cells.into_iter().for_each(|(cell, distance)| {
+            if let Some(index) = grid.cell_index(cell) {
+                distances.by_index[index] = distance;
+                distances.reached.push((cell, distance));
             }
-            return false;
-        })
-        .filter_map(|(_, neighbour)| neighbour)
-        .collect();
+        });
+        // Sorting is stable, so cells as far away stay in the order they came in.
+        distances.reached.sort_by_key(|(_, distance)| *distance);
+        distances
+    }
+
+    pub fn get<G: Grid<Cell = C> + ?Sized>(&self, grid: &G, cell: C) -> Option<i32> {
+        let distance = *self.by_index.get(grid.cell_index(cell)?)?;
+        (distance >= 0).then_some(distance)
+    }
+
+    // The number of cells that could be reached, including the start.
+    pub fn len(&self) -> usize {
+        self.reached.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reached.is_empty()
+    }
+
+    // Every cell that could be reached with its distance, nearest first. The
+    // order is always the same for the same maze.
+    pub fn iter(&self) -> impl Iterator<Item = (C, i32)> + '_ {
+        self.reached.iter().copied()
+    }
+
+    // The cell that's furthest away. When several are as far away, it's the last
+    // one that was reached.
+    pub fn get_most_distant(&self) -> Option<(C, i32)> {
+        self.reached.last().copied()
+    }
 }
 
-pub fn dijkstra(
-    from: IVec2,
-    grid_map: &GridMap,
-    removed_walls: &HashSet<Wall>,
-) -> HashMap<IVec2, i32> {
-    let mut distances: HashMap<IVec2, i32> = HashMap::new();
-    let mut distance = 0;
-    distances.insert(from, distance);
-    let mut frontiers = get_traversable_neighbours(from, grid_map, removed_walls);
-
-    while frontiers.len() > 0 {
-        let mut next_frontiers: Vec<IVec2> = vec![];
-        distance = distance + 1;
-        frontiers.iter().for_each(|frontier| {
-            if !distances.contains_key(frontier) {
-                distances.insert(*frontier, distance);
-                let mut neighbours = get_traversable_neighbours(*frontier, grid_map, removed_walls);
-                next_frontiers.append(&mut neighbours);
+// Every passage has the same cost, so this is a breadth first search. Cells are
+// reached in order of distance, so the distances are the shortest ones even
+// when the maze has loops.
+pub fn dijkstra<G: Grid + ?Sized>(
+    from: G::Cell,
+    grid: &G,
+    removed_walls: &G::LinkSet,
+) -> Distances<G::Cell> {
+    let mut distances = Distances {
+        by_index: vec![-1; grid.cell_index_count()],
+        reached: Vec::with_capacity(grid.cell_count()),
+    };
+    let Some(from_index) = grid.cell_index(from) else {
+        return distances;
+    };
+    distances.by_index[from_index] = 0;
+    distances.reached.push((from, 0));
+
+    // The cells reached so far double as the queue, as they're added in order of
+    // distance.
+    let mut next = 0;
+    while let Some((cell, distance)) = distances.reached.get(next).copied() {
+        next += 1;
+        grid.for_each_linked_neighbour(cell, removed_walls, &mut |neighbour, neighbour_index| {
+            // Cells are given their distance as soon as they're reached, so
+            // they're only queued once.
+            if distances.by_index[neighbour_index] < 0 {
+                distances.by_index[neighbour_index] = distance + 1;
+                distances.reached.push((neighbour, distance + 1));
             }
         });
-        frontiers = next_frontiers;
     }
 
-    return distances;
+    distances
 }
 
-pub fn get_path(
-    from: IVec2,
-    to: IVec2,
-    distances: &HashMap<IVec2, i32>,
-    grid_map: &GridMap,
-    removed_walls: &HashSet<Wall>,
-) -> HashMap<IVec2, i32> {
+pub fn get_path<G: Grid + ?Sized>(
+    from: G::Cell,
+    to: G::Cell,
+    distances: &Distances<G::Cell>,
+    grid: &G,
+    removed_walls: &G::LinkSet,
+) -> HashMap<G::Cell, i32> {
+    // Walks back from `to`, always stepping to a neighbour one closer to `from`.
+    // With loops there can be more than one shortest path, any of them will do.
+    let Some(mut current_distance) = distances.get(grid, to) else {
+        // Couldn't find a path.
+        return HashMap::new();
+    };
+    let mut breadcrumbs = HashMap::with_capacity(current_distance as usize + 1);
     let mut current = to;
-    let mut breadcrumbs: HashMap<IVec2, i32> = HashMap::new();
-    breadcrumbs.insert(current, 0);
+    breadcrumbs.insert(current, current_distance);
 
     while current != from {
-        let current_distance = distances.get(&current);
-        if current_distance.is_none() {
+        let mut next = None;
+        grid.for_each_linked_neighbour(
+            current,
+            removed_walls,
+            &mut |neighbour, neighbour_index| {
+                if next.is_none() && distances.by_index[neighbour_index] == current_distance - 1 {
+                    next = Some(neighbour);
+                }
+            },
+        );
+
+        let Some(next) = next else {
             // Couldn't find a path.
+            // This shouldn't happen though, `to` was reached from `from`.
             break;
-        }
+        };
+        current = next;
+        current_distance -= 1;
+        breadcrumbs.insert(current, current_distance);
+    }
 
-        let neighbours = get_traversable_neighbours(current, grid_map, removed_walls);
-        let next = neighbours.iter().find(|neighbour| {
-            let neighbour_distance = distances.get(*neighbour);
+    breadcrumbs
+}
 
-            if let (Some(neighbour_distance), Some(current_distance)) =
-                (neighbour_distance, current_distance)
-            {
-                return neighbour_distance < current_distance;
-            } else {
-                return false;
-            }
+pub fn solve_maze<G: Grid + ?Sized>(
+    grid: &G,
+    removed_walls: &G::LinkSet,
+    inaccessible_cells: &HashSet<G::Cell>,
+) -> Solution<G::Cell>
+where
+    G::Cell: Default + 'static,
+{
+    // The following process determines a start cell and end cell that are
+    // the furthest apart on the map and finds the quickest path between those points.
+
+    // First pick a temporary start position from the last cell that's part of
+    // the maze, which is the north east corner of a square grid map unless that
+    // corner has been filled in or turned off.
+    let start = grid
+        .cells()
+        .filter(|cell_pos| !inaccessible_cells.contains(cell_pos))
+        .last()
+        .unwrap_or_default();
+
+    // The cell that's furthest from there is at one end of the longest path
+    // through the maze, so it's the actual start.
+    let distances = dijkstra(start, grid, removed_walls);
+    let (start, _) = distances.get_most_distant().unwrap_or((start, 0));
+
+    // The cell that's furthest from the start is at the other end, so it's the
+    // end, and these are the distances that are shown.
+    let distances = dijkstra(start, grid, removed_walls);
+    let (end, farthest_distance) = distances.get_most_distant().unwrap_or((start, 0));
+
+    // Store the path from the start to the end.
+    let path = get_path(start, end, &distances, grid, removed_walls);
+
+    Solution {
+        start,
+        end,
+        path,
+        distances,
+        farthest_distance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::GridMap;
+    use bevy::prelude::*;
+
+    #[test]
+    fn solves_a_corridor_from_end_to_end() {
+        // A single row with every wall between its cells removed.
+        let grid_map = GridMap::new(5, 1);
+        let mut removed_walls = grid_map.new_link_set();
+        (0..4).for_each(|x| {
+            removed_walls.extend(grid_map.inner_wall_from_cell_pos(IVec2::new(x, 0), Dir2::EAST));
         });
 
-        if let Some(next) = next {
-            current = *next;
-            breadcrumbs.insert(current, *current_distance.unwrap());
-        } else {
-            // Couldn't find a path.
-            // This shouldn't happen though, should've hit the earlier break.
-            // TODO: Handle errors.
-            break;
-        }
+        let distances = dijkstra(IVec2::new(1, 0), &grid_map, &removed_walls);
+        assert_eq!(distances.len(), 5);
+        assert_eq!(distances.get(&grid_map, IVec2::new(4, 0)), Some(3));
+        assert_eq!(distances.get(&grid_map, IVec2::new(1, 0)), Some(0));
+        assert_eq!(distances.get(&grid_map, IVec2::new(9, 0)), None);
+        assert_eq!(distances.get_most_distant(), Some((IVec2::new(4, 0), 3)));
+
+        let solution = solve_maze(&grid_map, &removed_walls, &HashSet::new());
+        assert_eq!(solution.farthest_distance, 4);
+        assert_eq!(solution.path.len(), 5);
+        assert_eq!(solution.distances.len(), 5);
+        let mut ends = [solution.start.x, solution.end.x];
+        ends.sort();
+        assert_eq!(ends, [0, 4]);
+        assert_eq!(solution.path.get(&solution.end), Some(&4));
     }
 
-    return breadcrumbs;
-}
+    #[test]
+    fn cells_behind_walls_are_not_reached() {
+        let grid_map = GridMap::new(3, 3);
+        let distances = dijkstra(IVec2::ZERO, &grid_map, &grid_map.new_link_set());
+        assert_eq!(distances.len(), 1);
+        assert_eq!(distances.get(&grid_map, IVec2::ONE), None);
+    }
+
+    // Timings are only meaningful with optimizations and depend on the machine,
+    // so this is left out of normal runs. Run it with
+    // `cargo test --release --no-default-features -- --ignored`.
+    #[test]
+    #[ignore]
+    fn carves_and_solves_a_very_large_maze_quickly() {
+        use crate::maze_builders::{
+            carve_recursive_backtracker_into_grid, carve_sidewinder_into_grid_map, SidewinderBias,
+        };
+        use crate::resources::WallSet;
+        use fastrand::Rng;
+        use std::time::{Duration, Instant};
+
+        type Carve = fn(&GridMap, &mut Rng) -> WallSet;
+
+        // Sidewinder carves row by row, while the recursive backtracker walks from
+        // cell to cell and has to keep track of the cells it's visited.
+        let builders: [(&str, Carve); 2] = [
+            ("Sidewinder", |grid_map, rng| {
+                carve_sidewinder_into_grid_map(grid_map, rng, SidewinderBias::default())
+            }),
+            (
+                "Recursive Backtracker",
+                carve_recursive_backtracker_into_grid::<GridMap>,
+            ),
+        ];
+
+        let grid_map = GridMap::new(2000, 2000);
+        for (name, carve) in builders {
+            let start = Instant::now();
+            let removed_walls = carve(&grid_map, &mut Rng::with_seed(0));
+            let solution = solve_maze(&grid_map, &removed_walls, &HashSet::new());
+            let elapsed = start.elapsed();
 
-pub fn get_most_distant(distance_map: &HashMap<IVec2, i32>) -> (IVec2, i32) {
-    let mut furthest = (IVec2 { x: 0, y: 0 }, 0);
-    for item in distance_map {
-        if *item.1 > furthest.1 {
-            furthest = (item.0.clone(), item.1.clone());
+            assert_eq!(removed_walls.len(), grid_map.cell_count() - 1, "{name}");
+            assert_eq!(solution.distances.len(), grid_map.cell_count(), "{name}");
+            assert!(elapsed < Duration::from_secs(1), "{name} took {elapsed:?}");
         }
     }
-    furthest
 }
@@ -0,0 +1,128 @@
+// A union-find over the indices `0..size`, e.g. cell indices from
+// `GridMap::cell_pos_to_index`. Useful for checking which cells are connected.
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+    set_count: usize,
+}
+
+impl DisjointSet {
+    pub fn new(size: usize) -> Self {
+        DisjointSet {
+            parents: (0..size).collect(),
+            sizes: vec![1; size],
+            set_count: size,
+        }
+    }
+
+    // Returns the representative element of the set that `element` belongs to.
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point every element on the way at the root, so later lookups are quicker.
+        let mut current = element;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    // Merges the sets containing `a` and `b`. Returns `false` if they were
+    // already in the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return false;
+        }
+
+        // Attach the smaller set to the larger one to keep the trees shallow.
+        let (larger, smaller) = if self.sizes[root_a] >= self.sizes[root_b] {
+            (root_a, root_b)
+        } else {
+            (root_b, root_a)
+        };
+        self.parents[smaller] = larger;
+        self.sizes[larger] += self.sizes[smaller];
+        self.set_count -= 1;
+
+        true
+    }
+
+    pub fn is_connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    pub fn set_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.sizes[root]
+    }
+
+    pub fn set_count(&self) -> usize {
+        self.set_count
+    }
+
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_with_every_element_in_its_own_set() {
+        let mut disjoint_set = DisjointSet::new(4);
+        assert_eq!(disjoint_set.len(), 4);
+        assert_eq!(disjoint_set.set_count(), 4);
+        for element in 0..4 {
+            assert_eq!(disjoint_set.find(element), element);
+            assert_eq!(disjoint_set.set_size(element), 1);
+        }
+        assert!(!disjoint_set.is_connected(0, 1));
+    }
+
+    #[test]
+    fn union_merges_sets() {
+        let mut disjoint_set = DisjointSet::new(5);
+        assert!(disjoint_set.union(0, 1));
+        assert!(disjoint_set.union(2, 3));
+        assert_eq!(disjoint_set.set_count(), 3);
+        assert!(disjoint_set.is_connected(0, 1));
+        assert!(!disjoint_set.is_connected(1, 2));
+
+        assert!(disjoint_set.union(1, 3));
+        assert_eq!(disjoint_set.set_count(), 2);
+        assert_eq!(disjoint_set.find(0), disjoint_set.find(2));
+        assert_eq!(disjoint_set.set_size(3), 4);
+        assert_eq!(disjoint_set.set_size(4), 1);
+    }
+
+    #[test]
+    fn union_of_the_same_set_does_nothing() {
+        let mut disjoint_set = DisjointSet::new(3);
+        disjoint_set.union(0, 1);
+        assert!(!disjoint_set.union(1, 0));
+        assert!(!disjoint_set.union(2, 2));
+        assert_eq!(disjoint_set.set_count(), 2);
+        assert_eq!(disjoint_set.set_size(0), 2);
+    }
+
+    #[test]
+    fn empty_set() {
+        let disjoint_set = DisjointSet::new(0);
+        assert!(disjoint_set.is_empty());
+        assert_eq!(disjoint_set.set_count(), 0);
+    }
+}
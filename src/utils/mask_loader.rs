@@ -0,0 +1,334 @@
+use crate::resources::{CellMask, CellMaskError, GridMap};
+use bevy::prelude::*;
+use image::{ColorType, DynamicImage, ImageFormat};
+use std::{fmt, fs, path::Path};
+
+#[derive(Debug)]
+pub enum MaskLoadError {
+    Io(std::io::Error),
+    Image(image::ImageError),
+    // The file extension isn't one of the supported text or image formats.
+    UnsupportedFileType(String),
+    UnsupportedPixelFormat(ColorType),
+    // Lines are numbered from 1, like in a text editor.
+    RaggedLine {
+        line: usize,
+        expected_length: usize,
+        length: usize,
+    },
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    InvalidMask(CellMaskError),
+}
+
+impl fmt::Display for MaskLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaskLoadError::Io(error) => write!(f, "couldn't read the mask file: {error}"),
+            MaskLoadError::Image(error) => write!(f, "couldn't decode the mask image: {error}"),
+            MaskLoadError::UnsupportedFileType(extension) => write!(
+                f,
+                "unsupported mask file type '{extension}', expected .txt, .png, .pgm, .ppm or .pbm"
+            ),
+            MaskLoadError::UnsupportedPixelFormat(color_type) => write!(
+                f,
+                "unsupported pixel format {color_type:?}, expected an 8 or 16 bit grayscale or RGB image"
+            ),
+            MaskLoadError::RaggedLine {
+                line,
+                expected_length,
+                length,
+            } => write!(
+                f,
+                "line {line} is {length} cells long, but the first line is {expected_length} cells long"
+            ),
+            MaskLoadError::InvalidCharacter {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "unexpected '{character}' at line {line}, column {column}, expected 'X' for off or '.' for on"
+            ),
+            MaskLoadError::InvalidMask(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for MaskLoadError {}
+
+impl From<std::io::Error> for MaskLoadError {
+    fn from(error: std::io::Error) -> Self {
+        MaskLoadError::Io(error)
+    }
+}
+
+impl From<CellMaskError> for MaskLoadError {
+    fn from(error: CellMaskError) -> Self {
+        MaskLoadError::InvalidMask(error)
+    }
+}
+
+impl From<image::ImageError> for MaskLoadError {
+    fn from(error: image::ImageError) -> Self {
+        MaskLoadError::Image(error)
+    }
+}
+
+// Loads a mask from a text or image file, picking the format from the file
+// extension, and returns a grid map the size of the file.
+pub fn load_grid_map_from_mask_file(path: &Path) -> Result<GridMap, MaskLoadError> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let mask = match extension.as_str() {
+        "txt" => mask_from_ascii(&fs::read_to_string(path)?)?,
+        "png" => mask_from_image_bytes(&fs::read(path)?, ImageFormat::Png)?,
+        "pgm" | "ppm" | "pbm" | "pnm" => mask_from_image_bytes(&fs::read(path)?, ImageFormat::Pnm)?,
+        _ => return Err(MaskLoadError::UnsupportedFileType(extension)),
+    };
+
+    Ok(GridMap::with_mask(mask)?)
+}
+
+// Each line is a row of cells, where 'X' is off and '.' is on. The first line is
+// the top (northern) row of the maze.
+pub fn mask_from_ascii(text: &str) -> Result<CellMask, MaskLoadError> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .collect();
+    // Trailing blank lines are left by most editors, so they're ignored.
+    let row_count = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |index| index + 1);
+    let column_count = lines.first().map_or(0, |line| line.chars().count());
+
+    let mut mask = CellMask::new(column_count as i32, row_count as i32);
+    for (line_index, line) in lines.iter().take(row_count).enumerate() {
+        let length = line.chars().count();
+        if length != column_count {
+            return Err(MaskLoadError::RaggedLine {
+                line: line_index + 1,
+                expected_length: column_count,
+                length,
+            });
+        }
+
+        for (column_index, character) in line.chars().enumerate() {
+            let is_enabled = match character {
+                '.' => true,
+                'X' => false,
+                _ => {
+                    return Err(MaskLoadError::InvalidCharacter {
+                        line: line_index + 1,
+                        column: column_index + 1,
+                        character,
+                    })
+                }
+            };
+            let cell_pos = IVec2 {
+                x: column_index as i32,
+                y: (row_count - 1 - line_index) as i32,
+            };
+            mask.set_enabled(cell_pos, is_enabled);
+        }
+    }
+
+    mask.validate()?;
+    Ok(mask)
+}
+
+// Each pixel is a cell, where dark pixels are off and light pixels are on. The
+// top row of pixels is the top (northern) row of the maze.
+pub fn mask_from_image_bytes(bytes: &[u8], format: ImageFormat) -> Result<CellMask, MaskLoadError> {
+    let image = image::load_from_memory_with_format(bytes, format)?;
+    mask_from_image(&image)
+}
+
+pub fn mask_from_image(image: &DynamicImage) -> Result<CellMask, MaskLoadError> {
+    match image.color() {
+        ColorType::L8
+        | ColorType::La8
+        | ColorType::Rgb8
+        | ColorType::Rgba8
+        | ColorType::L16
+        | ColorType::La16
+        | ColorType::Rgb16
+        | ColorType::Rgba16 => (),
+        color_type => return Err(MaskLoadError::UnsupportedPixelFormat(color_type)),
+    }
+
+    let luma = image.to_luma8();
+    let (width, height) = luma.dimensions();
+    let mut mask = CellMask::new(width as i32, height as i32);
+    luma.enumerate_pixels().for_each(|(x, y, pixel)| {
+        let cell_pos = IVec2 {
+            x: x as i32,
+            y: (height - 1 - y) as i32,
+        };
+        mask.set_enabled(cell_pos, pixel.0[0] >= 128);
+    });
+
+    mask.validate()?;
+    Ok(mask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    #[test]
+    fn ascii_mask_puts_the_first_line_at_the_top() {
+        let mask = mask_from_ascii("X..\n...\n").unwrap();
+        assert_eq!(mask.get_columns(), 3);
+        assert_eq!(mask.get_rows(), 2);
+        assert_eq!(mask.get_enabled_count(), 5);
+        assert!(!mask.is_enabled(IVec2::new(0, 1)));
+        assert!(mask.is_enabled(IVec2::new(0, 0)));
+    }
+
+    #[test]
+    fn ascii_mask_ignores_trailing_blank_lines_and_carriage_returns() {
+        let mask = mask_from_ascii("..\r\n.X\r\n\n\n").unwrap();
+        assert_eq!(mask.get_rows(), 2);
+        assert_eq!(mask.get_enabled_count(), 3);
+    }
+
+    #[test]
+    fn ascii_mask_rejects_ragged_lines() {
+        let error = mask_from_ascii("...\n..\n").unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::RaggedLine {
+                line: 2,
+                expected_length: 3,
+                length: 2
+            }
+        ));
+    }
+
+    #[test]
+    fn ascii_mask_rejects_invalid_characters() {
+        let error = mask_from_ascii("...\n.o.\n").unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::InvalidCharacter {
+                line: 2,
+                column: 2,
+                character: 'o'
+            }
+        ));
+    }
+
+    #[test]
+    fn ascii_mask_rejects_separate_regions() {
+        let error = mask_from_ascii(".X.\n.X.\n").unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::InvalidMask(CellMaskError::DisconnectedCells { region_count: 2 })
+        ));
+    }
+
+    #[test]
+    fn ascii_mask_rejects_masks_without_cells() {
+        let error = mask_from_ascii("XX\n").unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::InvalidMask(CellMaskError::NoEnabledCells)
+        ));
+    }
+
+    #[test]
+    fn image_mask_turns_dark_pixels_off() {
+        let image = GrayImage::from_fn(2, 2, |x, y| match (x, y) {
+            (1, 0) => Luma([0]),
+            _ => Luma([255]),
+        });
+        let mask = mask_from_image(&DynamicImage::ImageLuma8(image)).unwrap();
+        assert_eq!(mask.get_enabled_count(), 3);
+        assert!(!mask.is_enabled(IVec2::new(1, 1)));
+    }
+
+    #[test]
+    fn image_mask_rejects_separate_regions() {
+        let image = GrayImage::from_fn(3, 1, |x, _| Luma([if x == 1 { 0 } else { 255 }]));
+        let error = mask_from_image(&DynamicImage::ImageLuma8(image)).unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::InvalidMask(CellMaskError::DisconnectedCells { region_count: 2 })
+        ));
+    }
+
+    #[test]
+    fn unsupported_file_types_are_rejected() {
+        let Err(error) = load_grid_map_from_mask_file(Path::new("mask.gif")) else {
+            panic!("a .gif mask shouldn't load");
+        };
+        assert!(
+            matches!(error, MaskLoadError::UnsupportedFileType(extension) if extension == "gif")
+        );
+    }
+
+    #[test]
+    fn loads_the_example_masks() {
+        let grid_map = load_grid_map_from_mask_file(Path::new("masks/circle.txt")).unwrap();
+        assert!(grid_map.get_enabled_cell_count() > 0);
+    }
+
+    // In a bitmap 1 is black, so it's a cell that's turned off.
+    #[test]
+    fn plain_bitmap_mask_turns_ones_off() {
+        let mask = mask_from_image_bytes(b"P1\n3 2\n1 0 0\n0 0 0\n", ImageFormat::Pnm).unwrap();
+        assert_eq!(mask.get_columns(), 3);
+        assert_eq!(mask.get_rows(), 2);
+        assert_eq!(mask.get_enabled_count(), 5);
+        assert!(!mask.is_enabled(IVec2::new(0, 1)));
+        assert!(mask.is_enabled(IVec2::new(0, 0)));
+    }
+
+    // Binary bitmaps pack eight pixels into each byte, most significant bit first,
+    // and each row starts on a new byte.
+    #[test]
+    fn binary_bitmap_mask_turns_ones_off() {
+        let mut bytes = b"P4\n3 2\n".to_vec();
+        bytes.extend([0b1000_0000, 0b0000_0000]);
+        let mask = mask_from_image_bytes(&bytes, ImageFormat::Pnm).unwrap();
+        assert_eq!(mask.get_columns(), 3);
+        assert_eq!(mask.get_rows(), 2);
+        assert_eq!(mask.get_enabled_count(), 5);
+        assert!(!mask.is_enabled(IVec2::new(0, 1)));
+    }
+
+    #[test]
+    fn pnm_mask_rejects_malformed_headers() {
+        let headers: [&[u8]; 3] = [b"P1\n3\n", b"P9\n3 2\n", b"P1\nthree 2\n"];
+        for header in headers {
+            let error = mask_from_image_bytes(header, ImageFormat::Pnm).unwrap_err();
+            assert!(matches!(error, MaskLoadError::Image(_)), "{error}");
+        }
+    }
+
+    #[test]
+    fn pnm_mask_is_validated() {
+        let error = mask_from_image_bytes(b"P1\n3 1\n0 1 0\n", ImageFormat::Pnm).unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::InvalidMask(CellMaskError::DisconnectedCells { region_count: 2 })
+        ));
+
+        let error = mask_from_image_bytes(b"P1\n2 1\n1 1\n", ImageFormat::Pnm).unwrap_err();
+        assert!(matches!(
+            error,
+            MaskLoadError::InvalidMask(CellMaskError::NoEnabledCells)
+        ));
+    }
+}
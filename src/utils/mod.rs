@@ -1,3 +1,7 @@
-mod dijkstra;
+pub mod dijkstra;
+pub mod disjoint_set;
+pub mod mask_loader;
 
 pub use dijkstra::*;
+pub use disjoint_set::*;
+pub use mask_loader::*;
@@ -0,0 +1,42 @@
+// Uses the library the way a batch job would, from outside the crate and
+// without the app feature.
+use bevy::prelude::*;
+use fastrand::Rng;
+use mazes::maze_builders::{carve_wilson_into_grid, MazeBuilder, Wilson};
+use mazes::utils::{dijkstra, get_path};
+use mazes::{Grid, GridMap, Wall};
+
+#[test]
+fn mazes_can_be_carved_and_solved_without_a_window() {
+    let grid_map = GridMap::new(20, 15);
+    let removed_walls = carve_wilson_into_grid(&grid_map, &mut Rng::with_seed(0));
+    assert_eq!(removed_walls.len(), grid_map.cell_count() - 1);
+
+    let start = IVec2::ZERO;
+    let end = IVec2::new(19, 14);
+    let distances = dijkstra(start, &grid_map, &removed_walls);
+    assert_eq!(distances.len(), grid_map.cell_count());
+
+    // Each step along the path goes through a wall that was removed.
+    let path = get_path(start, end, &distances, &grid_map, &removed_walls);
+    assert_eq!(
+        path.len() as i32,
+        distances.get(&grid_map, end).unwrap() + 1
+    );
+    let mut path: Vec<(IVec2, i32)> = path.into_iter().collect();
+    path.sort_by_key(|(_, distance)| *distance);
+    for cells in path.windows(2) {
+        let wall: Wall = grid_map.wall_between(cells[0].0, cells[1].0).unwrap();
+        assert!(removed_walls.contains(&wall));
+    }
+}
+
+#[test]
+fn builders_are_reproducible_from_a_seed() {
+    let grid_map = GridMap::new(20, 15);
+    let first = Wilson.carve(&grid_map, &mut Rng::with_seed(7));
+    let second = Wilson.carve(&grid_map, &mut Rng::with_seed(7));
+    let other = Wilson.carve(&grid_map, &mut Rng::with_seed(8));
+    assert!(first == second);
+    assert!(first != other);
+}